@@ -0,0 +1,74 @@
+// Capture transactions into an indexer fixture (see tests/fixtures.rs).
+//
+// Usage (from contracts/solana, with the transactions on RPC_URL):
+//   npx ts-node crates/stratum-indexer/scripts/capture-fixture.ts <out.log> <signature>...
+//
+// Writes each successful transaction as `# slot <slot> <block_time>`, its
+// `meta.logMessages`, then one `> <program_id> <base58 data>` line per inner
+// instruction sent to a Stratum program (where `emit_cpi!` events land when
+// the programs are built with `event-cpi`). Failed transactions are skipped.
+
+import { Connection, PublicKey } from "@solana/web3.js";
+import * as fs from "fs";
+
+const PROGRAMS = [
+  "97VX5yBvf55TdgpV6Cmc7pXdgshWyeCrHvn71bX6CHcJ", // stratum
+  "4QYpTZoHt34x1JqtWD5fKo1UgUwFgCQoHCqKpCHFcfyT", // stratum_orderbook
+  "6TTbWd9hqr6D2ijnT7RPm3EZws32Uiyfn52FEaJUvw6r", // airdrop_example
+];
+
+async function capture(connection: Connection, signature: string): Promise<string | null> {
+  const tx = await connection.getTransaction(signature, {
+    commitment: "confirmed",
+    maxSupportedTransactionVersion: 0,
+  });
+  if (!tx || !tx.meta) {
+    throw new Error(`transaction ${signature} not found`);
+  }
+  if (tx.meta.err) {
+    console.warn(`skipping failed transaction ${signature}`);
+    return null;
+  }
+
+  const keys = tx.transaction.message.getAccountKeys({
+    accountKeysFromLookups: tx.meta.loadedAddresses,
+  });
+  const inner: string[] = [];
+  for (const group of tx.meta.innerInstructions ?? []) {
+    for (const ix of group.instructions) {
+      const programId = (keys.get(ix.programIdIndex) as PublicKey).toBase58();
+      if (PROGRAMS.indexOf(programId) >= 0) {
+        inner.push(`> ${programId} ${ix.data}`);
+      }
+    }
+  }
+
+  return [
+    `# slot ${tx.slot} ${tx.blockTime ?? 0}`,
+    ...(tx.meta.logMessages ?? []),
+    ...inner,
+  ].join("\n");
+}
+
+async function main() {
+  const [out, ...signatures] = process.argv.slice(2);
+  if (!out || signatures.length === 0) {
+    console.error("usage: capture-fixture.ts <out.log> <signature>...");
+    process.exit(1);
+  }
+
+  const connection = new Connection(process.env.RPC_URL ?? "http://127.0.0.1:8899", "confirmed");
+  const blocks: string[] = [];
+  for (const signature of signatures) {
+    const block = await capture(connection, signature);
+    if (block) {
+      blocks.push(block);
+    }
+  }
+  fs.writeFileSync(out, blocks.join("\n\n") + "\n");
+}
+
+main().catch((err) => {
+  console.error(err);
+  process.exit(1);
+});
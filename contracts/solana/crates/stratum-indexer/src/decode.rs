@@ -0,0 +1,298 @@
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+
+use airdrop_example::{
+    CampaignCleanedUp, CampaignCreated, CampaignExtended, CampaignPaused, CampaignResumed,
+    TokensClaimed,
+};
+use stratum::{
+    ArchiveSegmentCommitted, BitSet, BitUnset, BitfieldGenerationRolled, CheckpointProofVerified,
+    CleanupReceipt, CleanupRewardPaid, EventEpochClosed, LeaseRenewed, MerkleProofVerified,
+    RecordArchived, RecordOwnerProgram, RecordResurrected,
+};
+use stratum_orderbook::events::{
+    EpochCreated, EpochFinalized, EpochRootSubmitted, ExpiredOrderCleaned, OrderBookCreated,
+    OrderCancelled, OrderSettled, SettlementCleaned,
+};
+
+/// Errors from decoding event payloads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// `Program data:` payload is not valid base64
+    InvalidBase64,
+    /// Inner instruction data is not valid base58
+    InvalidBase58,
+    /// Discriminator matched but the borsh payload didn't deserialize
+    InvalidPayload(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidBase64 => write!(f, "invalid base64 event data"),
+            Self::InvalidBase58 => write!(f, "invalid base58 instruction data"),
+            Self::InvalidPayload(name) => write!(f, "invalid {name} payload"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Event types that can be pulled out of a `ProgramEvent`
+pub trait TypedEvent: Sized {
+    /// Borrow the event if `event` is of this type
+    fn from_event(event: &ProgramEvent) -> Option<&Self>;
+}
+
+/// Declares a per-program event enum decoded by Anchor discriminator.
+/// Events listed under `shared` are emitted by more than one program and
+/// get a hand-written `TypedEvent` impl.
+macro_rules! program_events {
+    (
+        $(#[$meta:meta])*
+        $name:ident($program_id:path) as ProgramEvent::$wrapper:ident {
+            $($variant:ident),* $(,)?
+        }
+        $(shared { $($shared:ident),* $(,)? })?
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($variant($variant),)*
+            $($($shared($shared),)*)?
+        }
+
+        impl $name {
+            /// Program that emits these events
+            pub const PROGRAM_ID: Pubkey = $program_id;
+
+            /// Decode `discriminator || borsh payload`.
+            /// Returns `Ok(None)` for discriminators this program doesn't emit.
+            pub fn decode(data: &[u8]) -> Result<Option<Self>, DecodeError> {
+                $(
+                    if let Some(mut payload) = data.strip_prefix(<$variant as Discriminator>::DISCRIMINATOR) {
+                        return <$variant as AnchorDeserialize>::deserialize(&mut payload)
+                            .map(|event| Some(Self::$variant(event)))
+                            .map_err(|_| DecodeError::InvalidPayload(stringify!($variant)));
+                    }
+                )*
+                $($(
+                    if let Some(mut payload) = data.strip_prefix(<$shared as Discriminator>::DISCRIMINATOR) {
+                        return <$shared as AnchorDeserialize>::deserialize(&mut payload)
+                            .map(|event| Some(Self::$shared(event)))
+                            .map_err(|_| DecodeError::InvalidPayload(stringify!($shared)));
+                    }
+                )*)?
+                Ok(None)
+            }
+
+            /// Event name, as in the program IDL
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant(_) => stringify!($variant),)*
+                    $($(Self::$shared(_) => stringify!($shared),)*)?
+                }
+            }
+        }
+
+        $(
+            impl TypedEvent for $variant {
+                fn from_event(event: &ProgramEvent) -> Option<&Self> {
+                    match event {
+                        ProgramEvent::$wrapper($name::$variant(e)) => Some(e),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+program_events! {
+    /// Events emitted by the `stratum` program
+    StratumEvent(stratum::ID) as ProgramEvent::Stratum {
+        CleanupReceipt,
+        MerkleProofVerified,
+        BitSet,
+        BitUnset,
+        BitfieldGenerationRolled,
+        CleanupRewardPaid,
+        LeaseRenewed,
+        CheckpointProofVerified,
+        RecordArchived,
+        RecordOwnerProgram,
+        RecordResurrected,
+        ArchiveSegmentCommitted,
+    }
+    shared {
+        EventEpochClosed,
+    }
+}
+
+program_events! {
+    /// Events emitted by the `stratum_orderbook` program
+    OrderBookEvent(stratum_orderbook::ID) as ProgramEvent::OrderBook {
+        OrderBookCreated,
+        EpochCreated,
+        EpochRootSubmitted,
+        EpochFinalized,
+        OrderSettled,
+        OrderCancelled,
+        ExpiredOrderCleaned,
+        SettlementCleaned,
+    }
+    shared {
+        EventEpochClosed,
+    }
+}
+
+/// Stratum checkpoints and order book settlement checkpoints both emit
+/// `EventEpochClosed`; `checkpoint` is the account holding the accumulator
+impl TypedEvent for EventEpochClosed {
+    fn from_event(event: &ProgramEvent) -> Option<&Self> {
+        match event {
+            ProgramEvent::Stratum(StratumEvent::EventEpochClosed(e))
+            | ProgramEvent::OrderBook(OrderBookEvent::EventEpochClosed(e)) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+program_events! {
+    /// Events emitted by the `airdrop_example` program
+    AirdropEvent(airdrop_example::ID) as ProgramEvent::Airdrop {
+        CampaignCreated,
+        TokensClaimed,
+        CampaignPaused,
+        CampaignResumed,
+        CampaignExtended,
+        CampaignCleanedUp,
+    }
+}
+
+/// An event from any of the three programs
+pub enum ProgramEvent {
+    Stratum(StratumEvent),
+    OrderBook(OrderBookEvent),
+    Airdrop(AirdropEvent),
+}
+
+impl ProgramEvent {
+    /// Decode an event emitted by `program_id`.
+    /// Returns `Ok(None)` for unknown programs and discriminators.
+    pub fn decode(program_id: &Pubkey, data: &[u8]) -> Result<Option<Self>, DecodeError> {
+        Ok(if *program_id == StratumEvent::PROGRAM_ID {
+            StratumEvent::decode(data)?.map(Self::Stratum)
+        } else if *program_id == OrderBookEvent::PROGRAM_ID {
+            OrderBookEvent::decode(data)?.map(Self::OrderBook)
+        } else if *program_id == AirdropEvent::PROGRAM_ID {
+            AirdropEvent::decode(data)?.map(Self::Airdrop)
+        } else {
+            None
+        })
+    }
+
+    /// Event name, as in the program IDL
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stratum(e) => e.name(),
+            Self::OrderBook(e) => e.name(),
+            Self::Airdrop(e) => e.name(),
+        }
+    }
+
+    /// Borrow the event as a concrete type
+    pub fn as_event<E: TypedEvent>(&self) -> Option<&E> {
+        E::from_event(self)
+    }
+}
+
+/// Decode an `emit_cpi!` inner instruction sent by `program_id` to itself.
+/// Returns `Ok(None)` if `data` isn't an event instruction.
+pub fn decode_cpi_event(program_id: &Pubkey, data: &[u8]) -> Result<Option<ProgramEvent>, DecodeError> {
+    match data.strip_prefix(anchor_lang::event::EVENT_IX_TAG_LE) {
+        Some(event_data) => ProgramEvent::decode(program_id, event_data),
+        None => Ok(None),
+    }
+}
+
+/// `decode_cpi_event` for base58 instruction data, as returned by RPC
+pub fn decode_cpi_event_base58(
+    program_id: &Pubkey,
+    data: &str,
+) -> Result<Option<ProgramEvent>, DecodeError> {
+    let data = bs58::decode(data)
+        .into_vec()
+        .map_err(|_| DecodeError::InvalidBase58)?;
+    decode_cpi_event(program_id, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+
+    #[test]
+    fn test_decode_by_program() {
+        let event = CampaignPaused {
+            campaign: Pubkey::new_unique(),
+            paused_by: Pubkey::new_unique(),
+        };
+        let data = event.data();
+
+        let decoded = ProgramEvent::decode(&airdrop_example::ID, &data).unwrap().unwrap();
+        assert_eq!(decoded.name(), "CampaignPaused");
+        assert_eq!(decoded.as_event::<CampaignPaused>().unwrap().campaign, event.campaign);
+        assert!(decoded.as_event::<TokensClaimed>().is_none());
+
+        // Discriminators are only looked up in the emitting program
+        assert!(ProgramEvent::decode(&stratum::ID, &data).unwrap().is_none());
+        assert!(ProgramEvent::decode(&Pubkey::new_unique(), &data).unwrap().is_none());
+
+        // Known discriminator with a short payload
+        assert_eq!(
+            ProgramEvent::decode(&airdrop_example::ID, &data[..20]).err(),
+            Some(DecodeError::InvalidPayload("CampaignPaused"))
+        );
+    }
+
+    #[test]
+    fn test_decode_shared_event() {
+        let event = EventEpochClosed {
+            checkpoint: Pubkey::new_unique(),
+            epoch: 3,
+            root: [7; 32],
+            event_count: 12,
+        };
+        for program_id in [stratum::ID, stratum_orderbook::ID] {
+            let decoded = ProgramEvent::decode(&program_id, &event.data()).unwrap().unwrap();
+            assert_eq!(decoded.name(), "EventEpochClosed");
+            assert_eq!(decoded.as_event::<EventEpochClosed>().unwrap().epoch, 3);
+        }
+    }
+
+    #[test]
+    fn test_decode_cpi_event() {
+        let event = SettlementCleaned {
+            settlement: Pubkey::new_unique(),
+            cleaner: Pubkey::new_unique(),
+            reward: 5000,
+        };
+        let mut ix_data = anchor_lang::event::EVENT_IX_TAG_LE.to_vec();
+        ix_data.extend(event.data());
+
+        let encoded = bs58::encode(&ix_data).into_string();
+        let decoded = decode_cpi_event_base58(&stratum_orderbook::ID, &encoded)
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded.as_event::<SettlementCleaned>().unwrap().reward, 5000);
+
+        // Regular instructions aren't events
+        assert!(decode_cpi_event(&stratum_orderbook::ID, &event.data()).unwrap().is_none());
+        assert_eq!(
+            decode_cpi_event_base58(&stratum_orderbook::ID, "0OIl").err(),
+            Some(DecodeError::InvalidBase58)
+        );
+    }
+}
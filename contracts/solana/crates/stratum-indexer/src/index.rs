@@ -0,0 +1,592 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anchor_lang::prelude::Pubkey;
+
+use airdrop_example::TokensClaimed;
+use stratum::events::{ArchivableEvent, DistributionSummary, HistorySummary};
+use stratum::merkle::{hash_nodes, MerkleProof};
+use stratum::{
+    checkpoint_proof, ArchiveSegmentCommitted, ArchivedRecord, EpochRoot, EventAccumulator,
+    EventEpochClosed, LeafVersion, RecordArchived, RecordOwnerProgram,
+};
+use stratum_orderbook::events::{
+    EpochCreated, EpochFinalized, EpochRootSubmitted, ExpiredOrderCleaned, OrderCancelled,
+    OrderSettled,
+};
+
+use crate::decode::{AirdropEvent, DecodeError, OrderBookEvent, ProgramEvent, StratumEvent, TypedEvent};
+use crate::logs::parse_logs;
+
+/// Where an event was observed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventContext {
+    /// Slot of the transaction
+    pub slot: u64,
+
+    /// Block time of the transaction (unix seconds)
+    pub block_time: i64,
+}
+
+/// An event in the indexer's log
+pub struct IndexedEvent {
+    /// Where the event was observed
+    pub context: EventContext,
+
+    /// Program that emitted the event
+    pub program_id: Pubkey,
+
+    /// The decoded event
+    pub event: ProgramEvent,
+}
+
+/// Orders of one order book epoch, as seen through events
+#[derive(Debug, Clone, Default)]
+pub struct EpochOrders {
+    /// Epoch account
+    pub epoch: Pubkey,
+
+    /// Committed order root (None until submitted)
+    pub merkle_root: Option<[u8; 32]>,
+
+    /// Number of orders committed in the root
+    pub order_count: u32,
+
+    /// Whether the epoch was finalized
+    pub finalized: bool,
+
+    /// Order indexes cancelled by their maker
+    pub cancelled: BTreeSet<u32>,
+
+    /// Order indexes cleaned up after expiry
+    pub expired: BTreeSet<u32>,
+}
+
+impl EpochOrders {
+    /// Order indexes neither cancelled nor cleaned up
+    pub fn live_orders(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.order_count).filter(|i| !self.cancelled.contains(i) && !self.expired.contains(i))
+    }
+}
+
+/// Locally rebuilt order book state
+#[derive(Default)]
+pub struct OrderBookState {
+    /// Trade history, as `OrderBook::history`
+    pub history: HistorySummary,
+
+    /// Fill size distribution, as `OrderBook::fill_distribution`
+    pub fill_distribution: DistributionSummary,
+
+    /// Settlement checkpoint, as `OrderBook::event_checkpoint`
+    pub checkpoint: EventAccumulator,
+
+    /// Settlement hashes per checkpoint epoch, in append order
+    pub checkpoint_events: BTreeMap<u64, Vec<[u8; 32]>>,
+
+    /// Closed checkpoint epochs, as the order book's `SettlementCheckpoint`s
+    pub closed_checkpoints: BTreeMap<u64, EpochRoot>,
+
+    /// Epochs by index
+    pub epochs: BTreeMap<u32, EpochOrders>,
+}
+
+impl OrderBookState {
+    /// Proof for the settlement at `index` in checkpoint `epoch`, for
+    /// `verify_checkpointed_event` style checks against the on-chain root
+    pub fn checkpoint_proof(&self, epoch: u64, index: u32) -> Option<MerkleProof> {
+        checkpoint_proof(self.checkpoint_events.get(&epoch)?, index)
+    }
+
+    /// Check a settlement hash against the current or a closed checkpoint epoch
+    pub fn verify_checkpointed(&self, epoch: u64, event_hash: [u8; 32], proof: &MerkleProof) -> bool {
+        match self.closed_checkpoints.get(&epoch) {
+            Some(closed) => closed.verify(event_hash, proof),
+            None => self.checkpoint.verify(epoch, event_hash, proof),
+        }
+    }
+}
+
+/// Archive tree rebuilt from `RecordArchived` and `ArchiveSegmentCommitted`
+/// events
+///
+/// Leaves are `ArchivedRecord::leaf_hash` in archive index order; odd
+/// layers duplicate their last node, as the TypeScript `MerkleTree` the
+/// archive roots are built with.
+#[derive(Default)]
+pub struct ArchiveTree {
+    /// Archived records by archive index
+    pub records: BTreeMap<u64, ArchivedRecord>,
+
+    /// Committed segments by segment index
+    pub segments: BTreeMap<u32, ArchiveSegmentCommitted>,
+}
+
+impl ArchiveTree {
+    /// Number of leaves, if indexes are contiguous from zero
+    pub fn len(&self) -> Option<u64> {
+        let len = self.records.len() as u64;
+        match self.records.keys().next_back() {
+            Some(&last) if last + 1 != len => None,
+            _ => Some(len),
+        }
+    }
+
+    /// Whether no records were archived
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    fn leaves(&self, start: u64, count: u64, version: LeafVersion) -> Option<Vec<[u8; 32]>> {
+        let end = start.checked_add(count)?;
+        let leaves: Vec<_> = self
+            .records
+            .range(start..end)
+            .map(|(_, r)| r.leaf_hash_for(version))
+            .collect();
+        (count > 0 && leaves.len() as u64 == count).then_some(leaves)
+    }
+
+    /// Root over the first `count` records, to compare with a committed
+    /// `ArchiveRegistry::merkle_root` / `archived_count`
+    pub fn root(&self, count: u64) -> Option<[u8; 32]> {
+        self.range_root(0, count, LeafVersion::Legacy)
+    }
+
+    /// Proof for `archive_index` in the tree over the first `count` records
+    pub fn proof(&self, archive_index: u64, count: u64) -> Option<MerkleProof> {
+        self.range_proof(archive_index, 0, count, LeafVersion::Legacy)
+    }
+
+    /// Root over `count` records from `start` hashed as `version` leaves,
+    /// to compare with a committed `ArchiveSegment::merkle_root`
+    pub fn range_root(&self, start: u64, count: u64, version: LeafVersion) -> Option<[u8; 32]> {
+        let mut level = self.leaves(start, count, version)?;
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+        }
+        level.pop()
+    }
+
+    /// Proof for `archive_index` in the tree over `count` records from
+    /// `start`. The proof's `leaf_index` is local to the range.
+    pub fn range_proof(
+        &self,
+        archive_index: u64,
+        start: u64,
+        count: u64,
+        version: LeafVersion,
+    ) -> Option<MerkleProof> {
+        let mut level = self.leaves(start, count, version)?;
+        let local_index = archive_index.checked_sub(start).filter(|&i| i < count)?;
+
+        let mut idx = local_index as usize;
+        let mut siblings = Vec::new();
+        while level.len() > 1 {
+            siblings.push(*level.get(idx ^ 1).unwrap_or(&level[idx]));
+            level = level
+                .chunks(2)
+                .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            idx /= 2;
+        }
+
+        Some(MerkleProof {
+            siblings,
+            leaf_index: local_index as u32,
+        })
+    }
+
+    /// Segment covering `archive_index` and the record's proof within it,
+    /// for `ResurrectionProof::in_segment`
+    pub fn segment_proof(&self, archive_index: u64) -> Option<(u32, MerkleProof)> {
+        let segment = self.segments.values().find(|s| {
+            archive_index >= s.start_index && archive_index - s.start_index < s.leaf_count
+        })?;
+        let proof = self.range_proof(
+            archive_index,
+            segment.start_index,
+            segment.leaf_count,
+            segment.leaf_version,
+        )?;
+        Some((segment.segment_index, proof))
+    }
+}
+
+/// Local index over decoded events
+///
+/// Feed successful transactions in order (`ingest_logs` or `apply`) and
+/// read back typed event streams and state rebuilt with the programs' own
+/// types: campaign claim histories, order book histories, checkpoints and
+/// epoch order sets, and archive trees.
+///
+/// Summaries use the transaction's block time where the program used the
+/// clock's unix timestamp, so `last_timestamp` can differ slightly; counts,
+/// values and hash chains match the on-chain accounts.
+#[derive(Default)]
+pub struct Indexer {
+    events: Vec<IndexedEvent>,
+
+    /// Claim history per campaign, as `Campaign::claim_history`
+    pub campaigns: BTreeMap<Pubkey, HistorySummary>,
+
+    /// Order book state per order book
+    pub order_books: BTreeMap<Pubkey, OrderBookState>,
+
+    /// Archive tree per archive registry
+    pub archives: BTreeMap<Pubkey, ArchiveTree>,
+
+    /// Epoch account -> (order book, epoch index)
+    epoch_accounts: BTreeMap<Pubkey, (Pubkey, u32)>,
+}
+
+fn event_hash<E: ArchivableEvent>(event: &E) -> [u8; 32] {
+    event
+        .compute_hash()
+        .expect("serializing an event into a Vec can't fail")
+}
+
+impl Indexer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode a transaction's log messages and apply its events.
+    /// Returns whether the logs were truncated.
+    ///
+    /// Only feed successful transactions (`meta.err` is null): a reverted
+    /// transaction's logs still hold the events emitted before it failed.
+    /// Logs that end in a top-level failure are skipped here, but truncated
+    /// logs can cut that line off.
+    pub fn ingest_logs<S: AsRef<str>>(
+        &mut self,
+        context: EventContext,
+        logs: &[S],
+    ) -> Result<bool, DecodeError> {
+        let parsed = parse_logs(logs)?;
+        if parsed.failed {
+            return Ok(parsed.truncated);
+        }
+        for logged in parsed.events {
+            self.apply(context, logged.program_id, logged.event);
+        }
+        Ok(parsed.truncated)
+    }
+
+    /// Apply one event (e.g. decoded from an `emit_cpi!` inner instruction)
+    pub fn apply(&mut self, context: EventContext, program_id: Pubkey, event: ProgramEvent) {
+        match &event {
+            ProgramEvent::Airdrop(AirdropEvent::TokensClaimed(e)) => self.on_claim(context, e),
+            ProgramEvent::OrderBook(e) => self.on_order_book_event(context, e),
+            ProgramEvent::Stratum(StratumEvent::RecordArchived(e)) => self.on_archived(context, e),
+            ProgramEvent::Stratum(StratumEvent::RecordOwnerProgram(e)) => self.on_owner_program(e),
+            ProgramEvent::Stratum(StratumEvent::ArchiveSegmentCommitted(e)) => {
+                self.archives
+                    .entry(e.registry)
+                    .or_default()
+                    .segments
+                    .insert(e.segment_index, e.clone());
+            }
+            _ => {}
+        }
+
+        self.events.push(IndexedEvent {
+            context,
+            program_id,
+            event,
+        });
+    }
+
+    /// All events, in order
+    pub fn events(&self) -> &[IndexedEvent] {
+        &self.events
+    }
+
+    /// Events of one type, in order
+    pub fn stream<E: TypedEvent + 'static>(&self) -> impl Iterator<Item = (EventContext, &E)> + '_ {
+        self.events
+            .iter()
+            .filter_map(|indexed| Some((indexed.context, indexed.event.as_event::<E>()?)))
+    }
+
+    /// Order book and epoch orders for an epoch account
+    pub fn epoch(&self, epoch: &Pubkey) -> Option<&EpochOrders> {
+        let (order_book, index) = self.epoch_accounts.get(epoch)?;
+        self.order_books.get(order_book)?.epochs.get(index)
+    }
+
+    fn epoch_mut(&mut self, epoch: &Pubkey) -> Option<&mut EpochOrders> {
+        let (order_book, index) = self.epoch_accounts.get(epoch)?;
+        self.order_books.get_mut(order_book)?.epochs.get_mut(index)
+    }
+
+    fn on_claim(&mut self, context: EventContext, event: &TokensClaimed) {
+        let history = self.campaigns.entry(event.campaign).or_default();
+        history.record(event.value(), context.slot, context.block_time);
+        history.chain_event(event_hash(event));
+    }
+
+    fn on_order_book_event(&mut self, context: EventContext, event: &OrderBookEvent) {
+        match event {
+            OrderBookEvent::OrderBookCreated(e) => {
+                self.order_books.entry(e.order_book).or_default();
+            }
+            OrderBookEvent::EpochCreated(e) => self.on_epoch_created(e),
+            OrderBookEvent::EpochRootSubmitted(e) => self.on_root_submitted(e),
+            OrderBookEvent::EpochFinalized(e) => self.on_epoch_finalized(e),
+            OrderBookEvent::OrderSettled(e) => self.on_settled(context, e),
+            OrderBookEvent::OrderCancelled(e) => self.on_cancelled(e),
+            OrderBookEvent::ExpiredOrderCleaned(e) => self.on_expired(e),
+            OrderBookEvent::EventEpochClosed(e) => self.on_checkpoint_closed(e),
+            _ => {}
+        }
+    }
+
+    fn on_epoch_created(&mut self, event: &EpochCreated) {
+        self.epoch_accounts
+            .insert(event.epoch, (event.order_book, event.epoch_index));
+        self.order_books
+            .entry(event.order_book)
+            .or_default()
+            .epochs
+            .entry(event.epoch_index)
+            .or_default()
+            .epoch = event.epoch;
+    }
+
+    fn on_root_submitted(&mut self, event: &EpochRootSubmitted) {
+        if let Some(epoch) = self.epoch_mut(&event.epoch) {
+            epoch.merkle_root = Some(event.merkle_root);
+            epoch.order_count = event.order_count;
+        }
+    }
+
+    fn on_epoch_finalized(&mut self, event: &EpochFinalized) {
+        if let Some(epoch) = self.epoch_mut(&event.epoch) {
+            epoch.finalized = true;
+            epoch.order_count = event.order_count;
+        }
+    }
+
+    fn on_settled(&mut self, context: EventContext, event: &OrderSettled) {
+        let hash = event_hash(event);
+        let book = self.order_books.entry(event.order_book).or_default();
+        book.history.record(event.value(), context.slot, context.block_time);
+        book.history.chain_event(hash);
+        book.fill_distribution.record(event.value(), context.slot, context.block_time);
+        book.fill_distribution.chain_event(hash);
+
+        // The program rejects settlements into a full epoch, so this only
+        // fails if the close event was missed
+        if let Ok((epoch, _)) = book.checkpoint.append(hash) {
+            book.checkpoint_events.entry(epoch).or_default().push(hash);
+        }
+    }
+
+    fn on_checkpoint_closed(&mut self, event: &EventEpochClosed) {
+        let book = self.order_books.entry(event.checkpoint).or_default();
+        if book.checkpoint.epoch == event.epoch {
+            let _ = book.checkpoint.close_epoch();
+        }
+        book.closed_checkpoints.insert(
+            event.epoch,
+            EpochRoot {
+                epoch: event.epoch,
+                root: event.root,
+                event_count: event.event_count,
+            },
+        );
+    }
+
+    fn on_cancelled(&mut self, event: &OrderCancelled) {
+        self.order_books
+            .entry(event.order_book)
+            .or_default()
+            .epochs
+            .entry(event.epoch_index)
+            .or_default()
+            .cancelled
+            .insert(event.order_index);
+    }
+
+    fn on_expired(&mut self, event: &ExpiredOrderCleaned) {
+        self.order_books
+            .entry(event.order_book)
+            .or_default()
+            .epochs
+            .entry(event.epoch_index)
+            .or_default()
+            .expired
+            .insert(event.order_index);
+    }
+
+    fn on_archived(&mut self, context: EventContext, event: &RecordArchived) {
+        // The event carries everything in the leaf except the slot,
+        // which is the archiving transaction's
+        let record = ArchivedRecord {
+            account: event.account,
+            owner: event.owner,
+            archive_index: event.archive_index,
+            archived_slot: context.slot,
+            archived_at: context.block_time,
+            data_hash: event.data_hash,
+            data_size: 0,
+            owner_program: Pubkey::default(),
+        };
+        self.archives
+            .entry(event.registry)
+            .or_default()
+            .records
+            .insert(event.archive_index, record);
+    }
+
+    fn on_owner_program(&mut self, event: &RecordOwnerProgram) {
+        // Emitted right after the record's `RecordArchived`
+        if let Some(record) = self
+            .archives
+            .get_mut(&event.registry)
+            .and_then(|tree| tree.records.get_mut(&event.archive_index))
+        {
+            record.owner_program = event.owner_program;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stratum::merkle::hash_leaf;
+
+    fn archived(index: u64) -> RecordArchived {
+        RecordArchived {
+            registry: Pubkey::default(),
+            account: Pubkey::new_unique(),
+            owner: Pubkey::default(),
+            archive_index: index,
+            data_hash: hash_leaf(&[index as u8]),
+        }
+    }
+
+    #[test]
+    fn test_archive_tree() {
+        let mut indexer = Indexer::new();
+        for i in 0..5 {
+            let context = EventContext {
+                slot: 100 + i,
+                block_time: 0,
+            };
+            indexer.apply(
+                context,
+                stratum::ID,
+                ProgramEvent::Stratum(StratumEvent::RecordArchived(archived(i))),
+            );
+        }
+
+        let tree = &indexer.archives[&Pubkey::default()];
+        assert_eq!(tree.len(), Some(5));
+        assert_eq!(tree.records[&3].archived_slot, 103);
+
+        for count in 1..=5 {
+            let root = tree.root(count).unwrap();
+            for i in 0..count {
+                let proof = tree.proof(i, count).unwrap();
+                assert!(proof.verify(root, tree.records[&i].leaf_hash()));
+            }
+        }
+        assert!(tree.root(6).is_none());
+        assert!(tree.proof(5, 5).is_none());
+
+        // Single leaf is its own root
+        assert_eq!(tree.root(1), Some(tree.records[&0].leaf_hash()));
+    }
+
+    #[test]
+    fn test_checkpoint_epoch_closed() {
+        let order_book = Pubkey::new_unique();
+        let mut indexer = Indexer::new();
+        let settle = |indexer: &mut Indexer, id: u64| {
+            let settled = OrderSettled {
+                order_book,
+                maker: Pubkey::default(),
+                taker: Pubkey::default(),
+                fill_amount: 10,
+                fill_price: 100,
+                maker_order_id: id,
+                taker_order_id: id + 1,
+            };
+            let hash = event_hash(&settled);
+            indexer.apply(
+                EventContext::default(),
+                stratum_orderbook::ID,
+                ProgramEvent::OrderBook(OrderBookEvent::OrderSettled(settled)),
+            );
+            hash
+        };
+
+        let hashes = [settle(&mut indexer, 0), settle(&mut indexer, 2)];
+        let book = &indexer.order_books[&order_book];
+        let closed = EventEpochClosed {
+            checkpoint: order_book,
+            epoch: 0,
+            root: book.checkpoint.current_root(),
+            event_count: 2,
+        };
+        indexer.apply(
+            EventContext::default(),
+            stratum_orderbook::ID,
+            ProgramEvent::OrderBook(OrderBookEvent::EventEpochClosed(closed)),
+        );
+        let late = settle(&mut indexer, 4);
+
+        let book = &indexer.order_books[&order_book];
+        assert_eq!(book.checkpoint.epoch, 1);
+        assert_eq!(book.closed_checkpoints[&0].event_count, 2);
+        for (i, hash) in hashes.iter().enumerate() {
+            assert!(book.verify_checkpointed(0, *hash, &book.checkpoint_proof(0, i as u32).unwrap()));
+        }
+        assert!(book.verify_checkpointed(1, late, &book.checkpoint_proof(1, 0).unwrap()));
+        assert!(!book.verify_checkpointed(0, late, &book.checkpoint_proof(1, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_archive_segments() {
+        let mut indexer = Indexer::new();
+        for i in 0..5 {
+            indexer.apply(
+                EventContext::default(),
+                stratum::ID,
+                ProgramEvent::Stratum(StratumEvent::RecordArchived(archived(i))),
+            );
+        }
+        let merkle_root = indexer.archives[&Pubkey::default()]
+            .range_root(2, 3, LeafVersion::WithOwnerProgram)
+            .unwrap();
+        indexer.apply(
+            EventContext::default(),
+            stratum::ID,
+            ProgramEvent::Stratum(StratumEvent::ArchiveSegmentCommitted(ArchiveSegmentCommitted {
+                registry: Pubkey::default(),
+                segment: Pubkey::new_unique(),
+                segment_index: 0,
+                start_index: 2,
+                leaf_count: 3,
+                merkle_root,
+                da_commitment: None,
+                leaf_version: LeafVersion::WithOwnerProgram,
+            })),
+        );
+
+        let tree = &indexer.archives[&Pubkey::default()];
+        for i in 2..5 {
+            let (segment_index, proof) = tree.segment_proof(i).unwrap();
+            assert_eq!(segment_index, 0);
+            assert_eq!(proof.leaf_index as u64, i - 2);
+            let leaf = tree.records[&i].leaf_hash_for(LeafVersion::WithOwnerProgram);
+            assert!(proof.verify(merkle_root, leaf));
+        }
+        assert!(tree.segment_proof(1).is_none());
+        assert!(tree.range_root(3, 3, LeafVersion::WithOwnerProgram).is_none());
+    }
+}
@@ -0,0 +1,18 @@
+//! Event decoder and local indexer for the Stratum programs
+//!
+//! - `decode`: Anchor event decoding for every `#[event]` in `stratum`,
+//!   `stratum_orderbook` and `airdrop_example`, from `Program data:` payloads
+//!   or `emit_cpi!` inner instruction data
+//! - `logs`: transaction log parsing, attributing events to the program
+//!   that emitted them
+//! - `index`: typed event streams and state rebuilt locally (claim
+//!   histories, order book histories and checkpoints, epoch order sets,
+//!   archive trees)
+
+pub mod decode;
+pub mod index;
+pub mod logs;
+
+pub use decode::*;
+pub use index::*;
+pub use logs::*;
@@ -0,0 +1,176 @@
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use base64::Engine;
+
+use crate::decode::{DecodeError, ProgramEvent};
+
+/// An event decoded from a transaction, with the program that emitted it
+pub struct LoggedEvent {
+    /// Program whose invocation logged the event
+    pub program_id: Pubkey,
+
+    /// The decoded event
+    pub event: ProgramEvent,
+}
+
+/// Events decoded from one transaction's log messages
+pub struct TransactionEvents {
+    /// Events in emission order
+    pub events: Vec<LoggedEvent>,
+
+    /// Whether the runtime truncated the logs (later `emit!` events are lost;
+    /// build with `event-cpi` and read inner instructions instead)
+    pub truncated: bool,
+
+    /// Whether a top-level instruction failed, i.e. the transaction reverted
+    /// and its events never took effect
+    pub failed: bool,
+}
+
+/// Decode every Stratum, order book and airdrop event in a transaction's
+/// log messages (`meta.logMessages`).
+///
+/// `Program data:` lines are attributed to the innermost program on the
+/// invocation stack, so events logged inside CPIs are decoded against the
+/// program that emitted them. Lines from other programs are skipped.
+///
+/// Failed transactions keep the logs of everything that ran before the
+/// error, including events. `failed` catches this when the failure line
+/// was logged, but truncated logs can hide it: check `meta.err` too.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Result<TransactionEvents, DecodeError> {
+    let mut stack: Vec<Pubkey> = Vec::new();
+    let mut events = Vec::new();
+    let mut failed = false;
+
+    for line in logs.iter().map(AsRef::as_ref) {
+        if line == "Log truncated" {
+            return Ok(TransactionEvents {
+                events,
+                truncated: true,
+                failed,
+            });
+        }
+
+        if let Some(data) = line.strip_prefix("Program data: ") {
+            let Some(program_id) = stack.last() else {
+                continue;
+            };
+            // `sol_log_data` logs each field separately; Anchor uses one
+            let Some(field) = data.split(' ').next() else {
+                continue;
+            };
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(field)
+                .map_err(|_| DecodeError::InvalidBase64)?;
+            if let Some(event) = ProgramEvent::decode(program_id, &bytes)? {
+                events.push(LoggedEvent {
+                    program_id: *program_id,
+                    event,
+                });
+            }
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            let mut words = rest.split(' ');
+            let (Some(id), Some(action)) = (words.next(), words.next()) else {
+                continue;
+            };
+            match action {
+                "invoke" => {
+                    if let Ok(program_id) = Pubkey::from_str(id) {
+                        stack.push(program_id);
+                    }
+                }
+                "success" => {
+                    stack.pop();
+                }
+                "failed:" => {
+                    stack.pop();
+                    failed |= stack.is_empty();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(TransactionEvents {
+        events,
+        truncated: false,
+        failed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+    use stratum_orderbook::events::SettlementCleaned;
+
+    use crate::index::{EventContext, Indexer};
+
+    fn data_line(event: &impl Event) -> String {
+        format!(
+            "Program data: {}",
+            base64::engine::general_purpose::STANDARD.encode(event.data())
+        )
+    }
+
+    #[test]
+    fn test_invocation_stack() {
+        let event = SettlementCleaned {
+            settlement: Pubkey::new_unique(),
+            cleaner: Pubkey::new_unique(),
+            reward: 7,
+        };
+        let other = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {} invoke [1]", stratum_orderbook::ID),
+            format!("Program {other} invoke [2]"),
+            // Logged by an unrelated program: not decoded
+            data_line(&event),
+            format!("Program {other} success"),
+            data_line(&event),
+            format!("Program {} success", stratum_orderbook::ID),
+        ];
+
+        let parsed = parse_logs(&logs).unwrap();
+        assert!(!parsed.truncated);
+        assert_eq!(parsed.events.len(), 1);
+        assert_eq!(parsed.events[0].program_id, stratum_orderbook::ID);
+
+        let truncated = parse_logs(&[logs[0].as_str(), "Log truncated", logs[4].as_str()]).unwrap();
+        assert!(truncated.truncated && truncated.events.is_empty());
+
+        assert!(parse_logs(&[logs[0].as_str(), "Program data: ***"]).is_err());
+    }
+
+    #[test]
+    fn test_failed_transaction() {
+        let event = SettlementCleaned {
+            settlement: Pubkey::new_unique(),
+            cleaner: Pubkey::new_unique(),
+            reward: 7,
+        };
+        let other = Pubkey::new_unique();
+        let mut logs = vec![
+            format!("Program {} invoke [1]", stratum_orderbook::ID),
+            data_line(&event),
+            format!("Program {other} invoke [2]"),
+            format!("Program {other} failed: custom program error: 0x1"),
+        ];
+
+        // An inner failure alone doesn't decide the outcome
+        assert!(!parse_logs(&logs).unwrap().failed);
+
+        logs.push(format!(
+            "Program {} failed: custom program error: 0x1",
+            stratum_orderbook::ID
+        ));
+        let parsed = parse_logs(&logs).unwrap();
+        assert!(parsed.failed);
+        assert_eq!(parsed.events.len(), 1);
+
+        let mut indexer = Indexer::new();
+        assert!(!indexer.ingest_logs(EventContext::default(), &logs).unwrap());
+        assert!(indexer.events().is_empty());
+    }
+}
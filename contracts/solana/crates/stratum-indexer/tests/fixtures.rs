@@ -0,0 +1,236 @@
+//! Fixture tests over transaction logs.
+//!
+//! Each fixture holds `meta.logMessages` of consecutive successful
+//! transactions, separated by blank lines, each preceded by
+//! `# slot <slot> <block_time>` and followed by a `> <program_id> <data>`
+//! line (base58 data) per inner instruction sent to a Stratum program.
+//!
+//! The fixtures are hand-assembled in the runtime's log format, with
+//! `Program data:` payloads and `emit_cpi!` instruction data encoded from
+//! the programs' event types; they are not captured from a validator.
+//! `airdrop.log` covers a truncated log, `orderbook-cpi.log` the
+//! `orderbook.log` transactions with the order book built with
+//! `event-cpi`. To replace them with captured transactions, run the flows
+//! against a local validator and pass their signatures to
+//! `scripts/capture-fixture.ts`, which writes this format.
+
+use anchor_lang::prelude::Pubkey;
+
+use airdrop_example::TokensClaimed;
+use stratum::events::{ArchivableEvent, HistorySummary};
+use stratum::merkle::verify_proof;
+use stratum::{CleanupRewardPaid, RecordArchived};
+use stratum_indexer::{decode_cpi_event_base58, parse_logs, EventContext, Indexer};
+use stratum_orderbook::events::{ExpiredOrderCleaned, OrderSettled, SettlementCleaned};
+
+struct Transaction {
+    context: EventContext,
+    logs: Vec<&'static str>,
+    inner: Vec<(Pubkey, &'static str)>,
+}
+
+fn transactions(fixture: &'static str) -> Vec<Transaction> {
+    fixture
+        .split("\n\n")
+        .map(|tx| {
+            let mut lines = tx.lines();
+            let header = lines.next().unwrap().strip_prefix("# slot ").unwrap();
+            let (slot, block_time) = header.split_once(' ').unwrap();
+            let (inner, logs): (Vec<_>, Vec<_>) = lines.partition(|l| l.starts_with("> "));
+            Transaction {
+                context: EventContext {
+                    slot: slot.parse().unwrap(),
+                    block_time: block_time.parse().unwrap(),
+                },
+                logs,
+                inner: inner
+                    .iter()
+                    .map(|l| {
+                        let (program_id, data) = l[2..].split_once(' ').unwrap();
+                        (program_id.parse().unwrap(), data)
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn index(fixture: &'static str) -> (Indexer, usize) {
+    let mut indexer = Indexer::new();
+    let mut truncated = 0;
+    for tx in transactions(fixture) {
+        if indexer.ingest_logs(tx.context, &tx.logs).unwrap() {
+            truncated += 1;
+        }
+        for (program_id, data) in &tx.inner {
+            if let Some(event) = decode_cpi_event_base58(program_id, data).unwrap() {
+                indexer.apply(tx.context, *program_id, event);
+            }
+        }
+    }
+    (indexer, truncated)
+}
+
+#[test]
+fn test_order_book_lifecycle() {
+    let (indexer, truncated) = index(include_str!("fixtures/orderbook.log"));
+    assert_eq!(truncated, 0);
+
+    let names: Vec<_> = indexer.events().iter().map(|e| e.event.name()).collect();
+    assert_eq!(
+        names,
+        [
+            "OrderBookCreated",
+            "EpochCreated",
+            "EpochRootSubmitted",
+            "OrderSettled",
+            "OrderSettled",
+            "OrderSettled",
+            "OrderCancelled",
+            "EpochFinalized",
+            "CleanupRewardPaid",
+            "ExpiredOrderCleaned",
+            "CleanupRewardPaid",
+            "SettlementCleaned",
+        ]
+    );
+
+    // Rewards paid inside the stratum CPI are attributed to stratum
+    let paid: Vec<_> = indexer.stream::<CleanupRewardPaid>().map(|(_, e)| e.paid).collect();
+    assert_eq!(paid, [5000, 1200]);
+    let nested = &indexer.events()[8];
+    assert_eq!(nested.program_id, stratum::ID);
+    assert_eq!(nested.context.slot, 1050);
+    assert_eq!(indexer.stream::<ExpiredOrderCleaned>().next().unwrap().1.reward, 5000);
+    assert_eq!(indexer.stream::<SettlementCleaned>().next().unwrap().1.reward, 1200);
+
+    // History and checkpoint match what settle_match recorded on-chain
+    let settled: Vec<_> = indexer.stream::<OrderSettled>().map(|(_, e)| e).collect();
+    let book = &indexer.order_books[&settled[0].order_book];
+    assert_eq!(book.history.total_count, 3);
+    assert_eq!(book.history.total_value, 390);
+    assert_eq!(book.history.min_value, 40);
+    assert_eq!(book.history.max_value, 250);
+    assert_eq!(book.history.last_slot, 1022);
+
+    let hashes: Vec<_> = settled.iter().map(|e| e.compute_hash().unwrap()).collect();
+    assert!(book.history.verify_chain_from([0; 32], 0, &hashes));
+    assert_eq!(book.fill_distribution.summary.last_event_hash, book.history.last_event_hash);
+    assert_eq!(book.fill_distribution.p90(), 250);
+    for (i, hash) in hashes.iter().enumerate() {
+        let proof = book.checkpoint_proof(0, i as u32).unwrap();
+        assert!(book.verify_checkpointed(0, *hash, &proof));
+    }
+
+    // Epoch order set: 6 committed, one cancelled, one expired
+    let epoch = &book.epochs[&0];
+    assert_eq!(indexer.epoch(&epoch.epoch).unwrap().order_count, 6);
+    assert_eq!(epoch.merkle_root, Some([0xab; 32]));
+    assert!(epoch.finalized);
+    assert_eq!(epoch.live_orders().collect::<Vec<_>>(), [0, 1, 3, 5]);
+}
+
+#[test]
+fn test_order_book_cpi_events() {
+    let fixture = include_str!("fixtures/orderbook-cpi.log");
+
+    // With `event-cpi` the order book logs no events; only the rewards the
+    // stratum CPI pays with `emit!` remain in the logs
+    for tx in transactions(fixture) {
+        let parsed = parse_logs(&tx.logs).unwrap();
+        assert!(parsed.events.iter().all(|e| e.program_id == stratum::ID));
+    }
+
+    let (from_cpi, truncated) = index(fixture);
+    assert_eq!(truncated, 0);
+    let (from_logs, _) = index(include_str!("fixtures/orderbook.log"));
+
+    let mut names: Vec<_> = from_cpi.events().iter().map(|e| e.event.name()).collect();
+    let mut expected: Vec<_> = from_logs.events().iter().map(|e| e.event.name()).collect();
+    names.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(names, expected);
+
+    // Same order book state either way
+    let settled = from_cpi.stream::<OrderSettled>().next().unwrap().1.order_book;
+    let (book, expected) = (&from_cpi.order_books[&settled], &from_logs.order_books[&settled]);
+    assert_eq!(book.history.total_count, 3);
+    assert_eq!(book.history.last_event_hash, expected.history.last_event_hash);
+    assert_eq!(book.history.sequence, expected.history.sequence);
+    let proof = book.checkpoint_proof(0, 2).unwrap();
+    let hash = from_cpi.stream::<OrderSettled>().nth(2).unwrap().1.compute_hash().unwrap();
+    assert!(book.verify_checkpointed(0, hash, &proof));
+    assert_eq!(
+        book.epochs[&0].live_orders().collect::<Vec<_>>(),
+        expected.epochs[&0].live_orders().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_campaign_claims() {
+    let (indexer, truncated) = index(include_str!("fixtures/airdrop.log"));
+    assert_eq!(truncated, 1);
+
+    // The truncated batch keeps only the claims logged before the cutoff
+    let claims: Vec<_> = indexer.stream::<TokensClaimed>().collect();
+    assert_eq!(claims.len(), 4);
+    assert_eq!(claims.iter().map(|(_, e)| e.total_claimed).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    assert_eq!(claims[3].0.slot, 2004);
+
+    let campaign = claims[0].1.campaign;
+    let history: &HistorySummary = &indexer.campaigns[&campaign];
+    assert_eq!(history.total_count, 4);
+    assert_eq!(history.total_value, 5500);
+    assert_eq!(history.max_value, 2500);
+    assert_eq!(history.last_timestamp, 1_700_000_300);
+
+    let hashes: Vec<_> = claims.iter().map(|(_, e)| e.compute_hash().unwrap()).collect();
+    assert!(history.verify_chain_from([0; 32], 0, &hashes));
+    assert!(!history.verify_chain_from([0; 32], 0, &hashes[..3]));
+}
+
+#[test]
+fn test_archive_tree() {
+    let (indexer, _) = index(include_str!("fixtures/stratum.log"));
+
+    let archived: Vec<_> = indexer.stream::<RecordArchived>().collect();
+    assert_eq!(archived.len(), 5);
+    let tree = &indexer.archives[&archived[0].1.registry];
+    assert_eq!(tree.len(), Some(5));
+
+    // Leaves take the archiving transaction's slot
+    for (context, event) in &archived {
+        let record = &tree.records[&event.archive_index];
+        assert_eq!(record.archived_slot, context.slot);
+        assert_eq!(record.data_hash, event.data_hash);
+        // Archived chunks are Stratum accounts
+        assert_eq!(record.owner_program, stratum::ID);
+    }
+
+    // Every committed prefix yields proofs the program accepts
+    for count in 1..=5 {
+        let root = tree.root(count).unwrap();
+        for i in 0..count {
+            let proof = tree.proof(i, count).unwrap();
+            let leaf = tree.records[&i].leaf_hash();
+            assert!(verify_proof(&proof.siblings, root, leaf, i as u32));
+        }
+    }
+    assert_ne!(tree.root(4), tree.root(5));
+}
+
+#[test]
+fn test_logs_without_events() {
+    let logs = [
+        "Program 11111111111111111111111111111111 invoke [1]",
+        "Program 11111111111111111111111111111111 success",
+    ];
+    let parsed = parse_logs(&logs).unwrap();
+    assert!(parsed.events.is_empty() && !parsed.truncated);
+
+    // Events logged by unknown programs are ignored
+    let fixture = include_str!("fixtures/airdrop.log");
+    let foreign = fixture.replace(&airdrop_example::ID.to_string(), &Pubkey::new_unique().to_string());
+    let foreign: &'static str = Box::leak(foreign.into_boxed_str());
+    assert!(index(foreign).0.events().is_empty());
+}
@@ -267,7 +267,7 @@ module.exports = async function (provider: anchor.AnchorProvider) {
       const settlementTtl = new anchor.BN(24 * 60 * 60); // 1 day
 
       const tx = await orderbook.methods
-        .createOrderBook(tickSize, feeBps, settlementTtl)
+        .createOrderBook(tickSize, feeBps, settlementTtl, { timestamp: {} }, null)
         .accounts({
           orderBook: orderBookPda,
           baseVault: baseVaultPda,
@@ -1,9 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program::invoke_signed;
 use stratum::{
-    events::HistorySummary,
-    expiry::ExpiryConfig,
+    bitset::FixedBitSet,
+    emit_event,
+    events::{ArchivableEvent, EventRing, HistorySummary},
+    expiry::{ExpiryConfig, ExpiryUnit},
     merkle::{hash_leaf, verify_proof},
+    migrate::{migrate_account, LegacyExpiryConfig, LegacyHistorySummary},
+    program::Stratum,
+    record_event,
+    reward_pool::RewardPool,
 };
 
 declare_id!("6TTbWd9hqr6D2ijnT7RPm3EZws32Uiyfn52FEaJUvw6r");
@@ -56,7 +62,7 @@ pub mod airdrop_example {
         campaign.amount_per_claim = amount_per_claim;
 
         // Calculate required chunks for bitfield tracking
-        campaign.chunks_required = ((total_recipients + 2047) / 2048) as u32;
+        campaign.chunks_required = total_recipients.div_ceil(ClaimChunk::BITS_PER_CHUNK as u64) as u32;
         campaign.chunks_created = 0;
 
         // Setup expiry
@@ -69,6 +75,7 @@ pub mod airdrop_example {
             },
             grace_period: 86400, // 1 day grace period
             cleanup_reward: 10000, // 0.00001 SOL reward for cleanup
+            unit: ExpiryUnit::Timestamp,
         };
 
         // Initialize history tracking
@@ -77,8 +84,9 @@ pub mod airdrop_example {
         campaign.is_active = true;
         campaign.bump = ctx.bumps.campaign;
         campaign.vault_bump = ctx.bumps.vault;
+        campaign.has_recent_claims = false;
 
-        emit!(CampaignCreated {
+        emit_event!(ctx, CampaignCreated {
             campaign: campaign.key(),
             authority: campaign.authority,
             token_mint: campaign.token_mint,
@@ -125,6 +133,15 @@ pub mod airdrop_example {
         Ok(())
     }
 
+    /// Create the recent-claims ring for a campaign. Once it exists,
+    /// `claim` / `claim_variable` require it so it holds every claim.
+    pub fn create_recent_claims(ctx: Context<CreateRecentClaims>) -> Result<()> {
+        let mut recent = ctx.accounts.recent_claims.load_init()?;
+        recent.campaign = ctx.accounts.campaign.key();
+        ctx.accounts.campaign.has_recent_claims = true;
+        Ok(())
+    }
+
     /// Claim airdrop tokens with merkle proof
     ///
     /// # Arguments
@@ -143,14 +160,13 @@ pub mod airdrop_example {
         );
 
         // Verify chunk index matches
-        let expected_chunk = index / 2048;
+        let (expected_chunk, local_index) = ClaimChunk::split_index(index);
         require!(
             chunk.chunk_index == expected_chunk,
             AirdropError::WrongClaimChunk
         );
 
         // Check not already claimed (bitfield)
-        let local_index = (index % 2048) as u16;
         require!(!chunk.is_set(local_index), AirdropError::AlreadyClaimed);
 
         // Verify merkle proof
@@ -181,24 +197,33 @@ pub mod airdrop_example {
             signer,
         )?;
 
-        // Update history (minimal on-chain state)
+        // Update history and recent claims (minimal on-chain state)
         let campaign = &mut ctx.accounts.campaign;
         let amount = campaign.amount_per_claim;
         let total_recipients = campaign.total_recipients;
-        campaign.claim_history.record(
-            amount,
-            clock.slot,
-            clock.unix_timestamp,
-        );
-        let total_claimed = campaign.claim_history.total_count;
-
-        emit!(TokensClaimed {
+        let total_claimed = campaign.claim_history.total_count.saturating_add(1);
+        let claimed = TokensClaimed {
             campaign: campaign.key(),
             claimer: ctx.accounts.claimer.key(),
             index,
             amount,
             total_claimed,
-        });
+        };
+
+        let mut recent_claims = ctx
+            .accounts
+            .recent_claims
+            .as_ref()
+            .map(|r| r.load_mut())
+            .transpose()?;
+        record_event!(
+            claimed,
+            campaign.claim_history,
+            recent_claims.as_deref_mut().map(|r| &mut r.ring)
+        );
+        drop(recent_claims);
+
+        emit_event!(ctx, claimed);
 
         msg!(
             "Claimed {} tokens (claim #{} of {})",
@@ -231,14 +256,13 @@ pub mod airdrop_example {
         );
 
         // Verify chunk index matches
-        let expected_chunk = index / 2048;
+        let (expected_chunk, local_index) = ClaimChunk::split_index(index);
         require!(
             chunk.chunk_index == expected_chunk,
             AirdropError::WrongClaimChunk
         );
 
         // Check not already claimed
-        let local_index = (index % 2048) as u16;
         require!(!chunk.is_set(local_index), AirdropError::AlreadyClaimed);
 
         // Verify merkle proof with pubkey + amount as leaf
@@ -272,18 +296,30 @@ pub mod airdrop_example {
             signer,
         )?;
 
-        // Update history
+        // Update history and recent claims
         let campaign = &mut ctx.accounts.campaign;
-        campaign.claim_history.record(amount, clock.slot, clock.unix_timestamp);
-        let total_claimed = campaign.claim_history.total_count;
-
-        emit!(TokensClaimed {
+        let claimed = TokensClaimed {
             campaign: campaign.key(),
             claimer: ctx.accounts.claimer.key(),
             index,
             amount,
-            total_claimed,
-        });
+            total_claimed: campaign.claim_history.total_count.saturating_add(1),
+        };
+
+        let mut recent_claims = ctx
+            .accounts
+            .recent_claims
+            .as_ref()
+            .map(|r| r.load_mut())
+            .transpose()?;
+        record_event!(
+            claimed,
+            campaign.claim_history,
+            recent_claims.as_deref_mut().map(|r| &mut r.ring)
+        );
+        drop(recent_claims);
+
+        emit_event!(ctx, claimed);
 
         Ok(())
     }
@@ -293,7 +329,7 @@ pub mod airdrop_example {
         let campaign = &mut ctx.accounts.campaign;
         campaign.is_active = false;
 
-        emit!(CampaignPaused {
+        emit_event!(ctx, CampaignPaused {
             campaign: campaign.key(),
             paused_by: ctx.accounts.authority.key(),
         });
@@ -306,7 +342,7 @@ pub mod airdrop_example {
         let campaign = &mut ctx.accounts.campaign;
         campaign.is_active = true;
 
-        emit!(CampaignResumed {
+        emit_event!(ctx, CampaignResumed {
             campaign: campaign.key(),
             resumed_by: ctx.accounts.authority.key(),
         });
@@ -326,7 +362,7 @@ pub mod airdrop_example {
             .checked_add(additional_seconds)
             .ok_or(AirdropError::Overflow)?;
 
-        emit!(CampaignExtended {
+        emit_event!(ctx, CampaignExtended {
             campaign: campaign.key(),
             new_expires_at: campaign.expiry.expires_at,
         });
@@ -337,7 +373,7 @@ pub mod airdrop_example {
     /// Cleanup expired campaign and reclaim remaining tokens
     ///
     /// Anyone can call after expiry + grace period
-    /// Caller receives cleanup_reward as incentive
+    /// Caller receives cleanup_reward as incentive, paid from the reward pool
     pub fn cleanup_campaign(ctx: Context<CleanupCampaign>) -> Result<()> {
         let campaign = &ctx.accounts.campaign;
         let clock = Clock::get()?;
@@ -363,16 +399,16 @@ pub mod airdrop_example {
         };
         drop(vault_data);
 
+        let seeds = &[
+            b"campaign",
+            campaign.authority.as_ref(),
+            campaign.token_mint.as_ref(),
+            &[campaign.bump],
+        ];
+        let signer = &[&seeds[..]];
+
         // Transfer remaining tokens to authority
         if remaining > 0 {
-            let seeds = &[
-                b"campaign",
-                campaign.authority.as_ref(),
-                campaign.token_mint.as_ref(),
-                &[campaign.bump],
-            ];
-            let signer = &[&seeds[..]];
-
             spl_transfer(
                 ctx.accounts.vault.to_account_info(),
                 ctx.accounts.authority_token_account.to_account_info(),
@@ -383,14 +419,25 @@ pub mod airdrop_example {
             )?;
         }
 
-        // Pay cleanup reward to caller
-        let reward = campaign.expiry.cleanup_reward;
-        if reward > 0 {
-            **ctx.accounts.campaign.to_account_info().try_borrow_mut_lamports()? -= reward;
-            **ctx.accounts.cleaner.to_account_info().try_borrow_mut_lamports()? += reward;
+        // Pay cleanup reward to caller from the campaign's reward pool
+        let mut reward = 0;
+        if campaign.expiry.cleanup_reward > 0 {
+            reward = stratum::cpi::pay_cleanup_reward(
+                CpiContext::new_with_signer(
+                    ctx.accounts.stratum_program.to_account_info(),
+                    stratum::cpi::accounts::PayCleanupReward {
+                        reward_pool: ctx.accounts.reward_pool.to_account_info(),
+                        consumer: ctx.accounts.campaign.to_account_info(),
+                        recipient: ctx.accounts.cleaner.to_account_info(),
+                    },
+                    signer,
+                ),
+                campaign.expiry.cleanup_reward,
+            )?
+            .get();
         }
 
-        emit!(CampaignCleanedUp {
+        emit_event!(ctx, CampaignCleanedUp {
             campaign: campaign.key(),
             cleaner: ctx.accounts.cleaner.key(),
             remaining_tokens: remaining,
@@ -406,6 +453,29 @@ pub mod airdrop_example {
 
         Ok(())
     }
+
+    /// Migrate a campaign created before `ExpiryConfig::unit`,
+    /// `HistorySummary::sequence` and `has_recent_claims` to the current
+    /// layout (authority only, who pays the extra rent)
+    ///
+    /// Current campaigns are left unchanged.
+    pub fn migrate_campaign(ctx: Context<MigrateCampaign>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+
+        migrate_account(
+            &ctx.accounts.campaign.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            Campaign::LEGACY_SPACE,
+            Campaign::SPACE,
+            |legacy: LegacyCampaign| {
+                require_keys_eq!(legacy.authority, authority, AirdropError::Unauthorized);
+                Ok(Campaign::from(legacy))
+            },
+        )?;
+
+        Ok(())
+    }
 }
 
 // =============================================================================
@@ -484,6 +554,9 @@ pub struct Campaign {
     pub bump: u8,
     /// Vault PDA bump
     pub vault_bump: u8,
+
+    /// Whether the recent-claims ring exists (then claims require it)
+    pub has_recent_claims: bool,
 }
 
 impl Campaign {
@@ -496,14 +569,71 @@ impl Campaign {
         8 + // amount_per_claim
         4 + // chunks_required
         4 + // chunks_created
-        (8 + 8 + 8 + 8) + // expiry (ExpiryConfig)
-        (8 + 16 + 8 + 8 + 8 + 8 + 32) + // claim_history (HistorySummary)
+        (8 + 8 + 8 + 8 + 1) + // expiry (ExpiryConfig)
+        (8 + 16 + 8 + 8 + 8 + 8 + 32 + 8) + // claim_history (HistorySummary)
+        1 + // is_active
+        1 + // bump
+        1 + // vault_bump
+        1; // has_recent_claims
+
+    /// Size of a `LegacyCampaign` account
+    pub const LEGACY_SPACE: usize = 8 + // discriminator
+        32 + // authority
+        32 + // token_mint
+        32 + // vault
+        32 + // merkle_root
+        8 + // total_recipients
+        8 + // amount_per_claim
+        4 + // chunks_required
+        4 + // chunks_created
+        LegacyExpiryConfig::SPACE + // expiry
+        LegacyHistorySummary::SPACE + // claim_history
         1 + // is_active
         1 + // bump
         1; // vault_bump
 }
 
-/// Claim tracking chunk using Stratum's bitfield pattern
+/// `Campaign` as laid out before `ExpiryConfig::unit`,
+/// `HistorySummary::sequence` and `has_recent_claims` were added
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyCampaign {
+    pub authority: Pubkey,
+    pub token_mint: Pubkey,
+    pub vault: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub total_recipients: u64,
+    pub amount_per_claim: u64,
+    pub chunks_required: u32,
+    pub chunks_created: u32,
+    pub expiry: LegacyExpiryConfig,
+    pub claim_history: LegacyHistorySummary,
+    pub is_active: bool,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl From<LegacyCampaign> for Campaign {
+    fn from(legacy: LegacyCampaign) -> Self {
+        Self {
+            authority: legacy.authority,
+            token_mint: legacy.token_mint,
+            vault: legacy.vault,
+            merkle_root: legacy.merkle_root,
+            total_recipients: legacy.total_recipients,
+            amount_per_claim: legacy.amount_per_claim,
+            chunks_required: legacy.chunks_required,
+            chunks_created: legacy.chunks_created,
+            expiry: legacy.expiry.into(),
+            claim_history: legacy.claim_history.into(),
+            is_active: legacy.is_active,
+            bump: legacy.bump,
+            vault_bump: legacy.vault_bump,
+            has_recent_claims: false,
+        }
+    }
+}
+
+/// Claim tracking chunk using Stratum's FixedBitSet semantics
 #[account]
 pub struct ClaimChunk {
     /// Campaign this chunk belongs to
@@ -526,33 +656,51 @@ impl ClaimChunk {
         2 + // set_count
         1; // bump
 
-    pub fn is_set(&self, index: u16) -> bool {
-        if index >= 2048 {
-            return false;
-        }
-        let byte_idx = (index / 8) as usize;
-        let bit_idx = index % 8;
-        (self.bits[byte_idx] >> bit_idx) & 1 == 1
+    pub const BITS_PER_CHUNK: u32 = 2048;
+}
+
+impl FixedBitSet for ClaimChunk {
+    const CAPACITY: u32 = Self::BITS_PER_CHUNK;
+
+    fn bits(&self) -> &[u8] {
+        &self.bits
     }
 
-    pub fn set(&mut self, index: u16) -> Result<()> {
-        require!(index < 2048, AirdropError::InvalidClaimIndex);
-        let byte_idx = (index / 8) as usize;
-        let bit_idx = index % 8;
+    fn bits_mut(&mut self) -> &mut [u8] {
+        &mut self.bits
+    }
 
-        if !self.is_set(index) {
-            self.bits[byte_idx] |= 1 << bit_idx;
-            self.set_count += 1;
-        }
+    fn cached_count(&self) -> u16 {
+        self.set_count
+    }
 
-        Ok(())
+    fn cached_count_mut(&mut self) -> &mut u16 {
+        &mut self.set_count
     }
 }
 
+/// Last 64 claims of a campaign, for dashboards without an indexer.
+/// Zero-copy so recording a claim doesn't deserialize the whole ring.
+#[account(zero_copy)]
+pub struct RecentClaims {
+    /// Parent campaign
+    pub campaign: Pubkey,
+
+    /// Recent `TokensClaimed` records (actor = claimer, value = amount)
+    pub ring: EventRing,
+}
+
+impl RecentClaims {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // campaign
+        EventRing::SPACE; // ring
+}
+
 // =============================================================================
 // Account Contexts
 // =============================================================================
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct CreateCampaign<'info> {
     #[account(
@@ -611,19 +759,47 @@ pub struct CreateClaimChunk<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct CreateRecentClaims<'info> {
+    #[account(
+        mut,
+        seeds = [b"campaign", campaign.authority.as_ref(), campaign.token_mint.as_ref()],
+        bump = campaign.bump,
+        constraint = campaign.authority == authority.key() @ AirdropError::Unauthorized
+    )]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = RecentClaims::SPACE,
+        seeds = [b"recent_claims", campaign.key().as_ref()],
+        bump
+    )]
+    pub recent_claims: AccountLoader<'info, RecentClaims>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 #[instruction(proof: Vec<[u8; 32]>, index: u32)]
 pub struct Claim<'info> {
     #[account(
         mut,
         seeds = [b"campaign", campaign.authority.as_ref(), campaign.token_mint.as_ref()],
-        bump = campaign.bump
+        bump = campaign.bump,
+        constraint = !campaign.has_recent_claims || recent_claims.is_some()
+            @ AirdropError::MissingRecentClaims
     )]
     pub campaign: Account<'info, Campaign>,
 
     #[account(
         mut,
-        seeds = [b"claim_chunk", campaign.key().as_ref(), &(index / 2048).to_le_bytes()],
+        seeds = [b"claim_chunk", campaign.key().as_ref(), &(index / ClaimChunk::BITS_PER_CHUNK).to_le_bytes()],
         bump = claim_chunk.bump,
         constraint = claim_chunk.campaign == campaign.key() @ AirdropError::WrongClaimChunk
     )]
@@ -646,8 +822,17 @@ pub struct Claim<'info> {
     /// CHECK: Token program
     #[account(address = spl_token::ID)]
     pub token_program: AccountInfo<'info>,
+
+    /// Recent-claims ring; required once created for this campaign
+    #[account(
+        mut,
+        seeds = [b"recent_claims", campaign.key().as_ref()],
+        bump
+    )]
+    pub recent_claims: Option<AccountLoader<'info, RecentClaims>>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct AdminAction<'info> {
     #[account(
@@ -661,6 +846,7 @@ pub struct AdminAction<'info> {
     pub authority: Signer<'info>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct CleanupCampaign<'info> {
     #[account(
@@ -682,6 +868,15 @@ pub struct CleanupCampaign<'info> {
     #[account(mut)]
     pub authority_token_account: AccountInfo<'info>,
 
+    /// Reward pool funding the cleanup reward
+    #[account(
+        mut,
+        constraint = reward_pool.consumer == campaign.key() @ AirdropError::Unauthorized
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub stratum_program: Program<'info, Stratum>,
+
     /// Anyone can call cleanup, receives reward
     #[account(mut)]
     pub cleaner: Signer<'info>,
@@ -691,6 +886,19 @@ pub struct CleanupCampaign<'info> {
     pub token_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateCampaign<'info> {
+    /// CHECK: Owner, discriminator and layout are checked by `migrate_account`
+    #[account(mut)]
+    pub campaign: UncheckedAccount<'info>,
+
+    /// Campaign authority, pays rent for the added bytes
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 // =============================================================================
 // Events
 // =============================================================================
@@ -715,6 +923,20 @@ pub struct TokensClaimed {
     pub total_claimed: u64,
 }
 
+impl ArchivableEvent for TokensClaimed {
+    fn event_type(&self) -> &'static str {
+        "tokens_claimed"
+    }
+
+    fn value(&self) -> u64 {
+        self.amount
+    }
+
+    fn actor(&self) -> Pubkey {
+        self.claimer
+    }
+}
+
 #[event]
 pub struct CampaignPaused {
     pub campaign: Pubkey,
@@ -769,11 +991,9 @@ pub enum AirdropError {
     #[msg("Invalid chunk index")]
     InvalidChunkIndex,
 
-    #[msg("Invalid claim index")]
-    InvalidClaimIndex,
-
+    // 7 was `InvalidClaimIndex`; later codes keep their values
     #[msg("Wrong token mint")]
-    WrongTokenMint,
+    WrongTokenMint = 8,
 
     #[msg("Wrong token owner")]
     WrongTokenOwner,
@@ -786,4 +1006,7 @@ pub enum AirdropError {
 
     #[msg("Arithmetic overflow")]
     Overflow,
+
+    #[msg("Campaign has a recent-claims ring; pass it")]
+    MissingRecentClaims,
 }
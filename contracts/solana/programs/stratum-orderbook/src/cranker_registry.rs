@@ -9,7 +9,7 @@ pub struct CrankerRegistry {
     pub min_stake: u64,
     /// Slash percentage in basis points (e.g. 5000 = 50%)
     pub slash_bps: u16,
-    /// Challenge period in seconds after root submission
+    /// Challenge period after root submission, in the order book's `expiry_unit`
     pub challenge_period: i64,
     /// Number of registered active crankers
     pub cranker_count: u32,
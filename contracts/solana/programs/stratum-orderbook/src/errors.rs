@@ -95,4 +95,19 @@ pub enum OrderBookError {
 
     #[msg("Proposed root must differ from submitted root")]
     ChallengeRootSameAsSubmitted,
+
+    // --- Price Oracle Errors ---
+
+    #[msg("Price window must be greater than zero")]
+    InvalidPriceWindow,
+
+    // --- Checkpoint Errors ---
+
+    #[msg("Settlement is not in the order book's checkpoint")]
+    InvalidSettlementProof,
+
+    // --- Recent Trades Errors ---
+
+    #[msg("Order book has a recent-trades ring; pass it")]
+    MissingRecentTrades,
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use stratum::events::ArchivableEvent;
 
 #[event]
 pub struct OrderBookCreated {
@@ -41,6 +42,20 @@ pub struct OrderSettled {
     pub taker_order_id: u64,
 }
 
+impl ArchivableEvent for OrderSettled {
+    fn event_type(&self) -> &'static str {
+        "order_settled"
+    }
+
+    fn value(&self) -> u64 {
+        self.fill_amount
+    }
+
+    fn actor(&self) -> Pubkey {
+        self.taker
+    }
+}
+
 #[event]
 pub struct OrderCancelled {
     pub order_book: Pubkey,
@@ -1,7 +1,18 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program::invoke_signed;
-use stratum::merkle::{hash_struct, verify_proof};
-use stratum::expiry::ExpiryConfig;
+use stratum::bitset::FixedBitSet;
+use stratum::checkpoint::EventAccumulator;
+use stratum::merkle::{hash_struct, verify_proof, MerkleProof};
+use stratum::dynamic_expiry::RewardCurve;
+use stratum::{emit_event, record_event};
+use stratum::events::{
+    ArchivableEvent, DistributionSummary, HistorySummary, PriceAccumulator, PriceQuote,
+};
+use stratum::expiry::{ExpiryConfig, ExpiryUnit};
+use stratum::migrate::migrate_account;
+use stratum::program::Stratum;
+use stratum::reward_pool::RewardPool;
+use stratum::EventEpochClosed;
 
 pub mod challenge;
 pub mod cranker_registry;
@@ -35,6 +46,8 @@ pub mod stratum_orderbook {
         tick_size: u64,
         fee_bps: u16,
         _settlement_ttl_seconds: i64,
+        expiry_unit: ExpiryUnit,
+        cleanup_curve: Option<RewardCurve>,
     ) -> Result<()> {
         let ob = &mut ctx.accounts.order_book;
         let clock = Clock::get()?;
@@ -52,19 +65,26 @@ pub mod stratum_orderbook {
         ob.tick_size = tick_size;
         ob.fee_bps = fee_bps;
         ob.fee_vault = ctx.accounts.fee_vault.key();
-        ob.history = stratum::events::HistorySummary::default();
+        ob.history = HistorySummary::default();
         ob.settlement_expiry = ExpiryConfig {
             created_at: clock.unix_timestamp,
             expires_at: 0, // template, actual settlement receipts get their own
             grace_period: 86400, // 1 day
             cleanup_reward: 5000, // 5000 lamports
+            unit: ExpiryUnit::Timestamp,
         };
         ob.is_active = true;
         ob.bump = ctx.bumps.order_book;
         ob.base_vault_bump = ctx.bumps.base_vault;
         ob.quote_vault_bump = ctx.bumps.quote_vault;
-
-        emit!(OrderBookCreated {
+        ob.fill_distribution = DistributionSummary::default();
+        ob.has_recent_trades = false;
+        ob.expiry_unit = expiry_unit;
+        ob.cleanup_curve = cleanup_curve.unwrap_or(OrderBook::DEFAULT_CLEANUP_CURVE);
+        ob.price_oracle = PriceAccumulator::default();
+        ob.event_checkpoint = EventAccumulator::default();
+
+        emit_event!(ctx, OrderBookCreated {
             order_book: ob.key(),
             authority: ob.authority,
             base_mint: ob.base_mint,
@@ -99,7 +119,7 @@ pub mod stratum_orderbook {
 
         ob.current_epoch = ob.current_epoch.saturating_add(1);
 
-        emit!(EpochCreated {
+        emit_event!(ctx, EpochCreated {
             order_book: ob.key(),
             epoch: epoch.key(),
             epoch_index,
@@ -124,6 +144,15 @@ pub mod stratum_orderbook {
         Ok(())
     }
 
+    /// Create the recent-trades ring for an order book. Once it exists,
+    /// `settle_match` requires it so it holds every settlement.
+    pub fn create_recent_trades(ctx: Context<CreateRecentTrades>) -> Result<()> {
+        let mut recent = ctx.accounts.recent_trades.load_init()?;
+        recent.order_book = ctx.accounts.order_book.key();
+        ctx.accounts.order_book.has_recent_trades = true;
+        Ok(())
+    }
+
     /// Cranker submits a computed merkle root for an epoch's orders
     pub fn submit_epoch_root(
         ctx: Context<SubmitEpochRoot>,
@@ -142,7 +171,7 @@ pub mod stratum_orderbook {
         let ob = &mut ctx.accounts.order_book;
         ob.total_orders = ob.total_orders.saturating_add(order_count as u64);
 
-        emit!(EpochRootSubmitted {
+        emit_event!(ctx, EpochRootSubmitted {
             epoch: epoch.key(),
             merkle_root: root,
             order_count,
@@ -162,7 +191,7 @@ pub mod stratum_orderbook {
         epoch.is_finalized = true;
         epoch.finalized_at = clock.unix_timestamp;
 
-        emit!(EpochFinalized {
+        emit_event!(ctx, EpochFinalized {
             epoch: epoch.key(),
             epoch_index: epoch.epoch_index,
             order_count: epoch.order_count,
@@ -173,6 +202,7 @@ pub mod stratum_orderbook {
 
     /// Core matching instruction: verify both proofs, check bitfields,
     /// validate price, transfer tokens, update bitfield, emit event
+    #[allow(clippy::too_many_arguments)]
     pub fn settle_match(
         ctx: Context<SettleMatch>,
         maker_order: OrderLeaf,
@@ -323,10 +353,10 @@ pub mod stratum_orderbook {
 
         // If fill_amount == order amount, unset bitfield bits (fully filled)
         if fill_amount == maker_order.amount {
-            maker_chunk.set_inactive(maker_local);
+            maker_chunk.set_inactive(maker_local)?;
         }
         if fill_amount == taker_order.amount {
-            taker_chunk.set_inactive(taker_local);
+            taker_chunk.set_inactive(taker_local)?;
         }
 
         // Create settlement receipt
@@ -344,6 +374,7 @@ pub mod stratum_orderbook {
             expires_at: clock.unix_timestamp + 604800, // 1 week
             grace_period: ob.settlement_expiry.grace_period,
             cleanup_reward: ob.settlement_expiry.cleanup_reward,
+            unit: ExpiryUnit::Timestamp,
         };
         receipt.settled_at = clock.unix_timestamp;
         receipt.bump = ctx.bumps.settlement_receipt;
@@ -351,9 +382,11 @@ pub mod stratum_orderbook {
         // Update order book history
         let ob = &mut ctx.accounts.order_book;
         ob.total_settlements = ob.total_settlements.saturating_add(1);
-        ob.history.record(fill_amount, clock.slot, clock.unix_timestamp);
+        ob.price_oracle.update_at(clock.unix_timestamp, fill_price, fill_amount);
 
-        emit!(OrderSettled {
+        // Record in history, the settlement checkpoint and the fill
+        // distribution, then emit
+        let settled = OrderSettled {
             order_book: ob.key(),
             maker: maker_order.maker,
             taker: taker_order.maker,
@@ -361,7 +394,23 @@ pub mod stratum_orderbook {
             fill_price,
             maker_order_id: maker_order.order_id,
             taker_order_id: taker_order.order_id,
-        });
+        };
+        let mut recent_trades = ctx
+            .accounts
+            .recent_trades
+            .as_ref()
+            .map(|r| r.load_mut())
+            .transpose()?;
+        record_event!(
+            settled,
+            ob.history,
+            ob.event_checkpoint,
+            ob.fill_distribution,
+            recent_trades.as_deref_mut().map(|r| &mut r.ring)
+        );
+        drop(recent_trades);
+
+        emit_event!(ctx, settled);
 
         Ok(())
     }
@@ -399,7 +448,7 @@ pub mod stratum_orderbook {
         );
 
         // Unset the bit
-        chunk.set_inactive(local_index);
+        chunk.set_inactive(local_index)?;
 
         // Refund tokens to maker (from appropriate vault based on side)
         let ob = &ctx.accounts.order_book;
@@ -438,7 +487,7 @@ pub mod stratum_orderbook {
             }
         }
 
-        emit!(OrderCancelled {
+        emit_event!(ctx, OrderCancelled {
             order_book: ob.key(),
             maker: order.maker,
             order_id: order.order_id,
@@ -459,10 +508,12 @@ pub mod stratum_orderbook {
         let epoch = &ctx.accounts.epoch;
         let chunk = &mut ctx.accounts.order_chunk;
         let clock = Clock::get()?;
+        let expiry_unit = ctx.accounts.order_book.expiry_unit;
+        let now = expiry_unit.current(&clock);
 
         // Verify the order has expired
         require!(
-            order.expires_at > 0 && clock.unix_timestamp > order.expires_at,
+            order.expires_at > 0 && now > order.expires_at,
             OrderBookError::OrderNotExpired
         );
 
@@ -481,7 +532,7 @@ pub mod stratum_orderbook {
         );
 
         // Unset the bit
-        chunk.set_inactive(local_index);
+        chunk.set_inactive(local_index)?;
 
         // Refund tokens to maker
         let ob = &ctx.accounts.order_book;
@@ -518,33 +569,29 @@ pub mod stratum_orderbook {
             }
         }
 
-        // Pay dynamic cleanup reward to caller
-        // Base reward escalates over 24 hours (86400s) up to 10x, capped at account balance
-        let base_reward = ob.settlement_expiry.cleanup_reward;
-        let overdue = clock.unix_timestamp.saturating_sub(order.expires_at).max(0);
-        let escalation_period: i64 = 86400; // 24 hours
-        let max_multiplier: u64 = 10;
-
-        let reward = if escalation_period <= 0 || overdue <= 0 {
-            base_reward
-        } else {
-            let factor = if overdue >= escalation_period {
-                max_multiplier
-            } else {
-                1 + ((max_multiplier - 1) * overdue as u64) / escalation_period as u64
-            };
-            base_reward.saturating_mul(factor)
+        // Pay dynamic cleanup reward to caller, following the order book's curve
+        let order_expiry = ExpiryConfig {
+            created_at: order.created_at,
+            expires_at: order.expires_at,
+            grace_period: 0,
+            cleanup_reward: ob.settlement_expiry.cleanup_reward,
+            unit: expiry_unit,
         };
+        let reward = ob
+            .cleanup_reward_config(&order_expiry)
+            .calculate_dynamic_reward_at(now)?;
+
+        // Paid from the order book's reward pool, capped at its balance above rent
+        let capped_reward = pay_cleanup_reward(
+            ctx.accounts.stratum_program.to_account_info(),
+            ctx.accounts.reward_pool.to_account_info(),
+            ctx.accounts.order_book.to_account_info(),
+            ctx.accounts.cleaner.to_account_info(),
+            reward,
+            signer,
+        )?;
 
-        // Cap at available lamports
-        let available = ctx.accounts.order_book.to_account_info().lamports();
-        let capped_reward = reward.min(available.saturating_sub(890880)); // preserve rent-exempt minimum
-        if capped_reward > 0 {
-            **ctx.accounts.order_book.to_account_info().try_borrow_mut_lamports()? -= capped_reward;
-            **ctx.accounts.cleaner.to_account_info().try_borrow_mut_lamports()? += capped_reward;
-        }
-
-        emit!(ExpiredOrderCleaned {
+        emit_event!(ctx, ExpiredOrderCleaned {
             order_book: ob.key(),
             order_id: order.order_id,
             epoch_index: order.epoch_index,
@@ -556,10 +603,67 @@ pub mod stratum_orderbook {
         Ok(())
     }
 
+    /// TWAP/VWAP of fills over the last `window` seconds; fails if the
+    /// price history doesn't cover the window.
+    /// Returned via return data so other programs can read it over CPI.
+    pub fn get_price_quote(ctx: Context<GetPriceQuote>, window: i64) -> Result<PriceQuote> {
+        require!(window > 0, OrderBookError::InvalidPriceWindow);
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.order_book.price_oracle.quote_at(now, window)
+    }
+
+    /// Close the (non-empty) settlement checkpoint epoch, persisting its root
+    /// in a `SettlementCheckpoint` account so its settlements stay provable
+    /// with `verify_settlement`. Anyone may close a full epoch, since
+    /// settlements are rejected until it is closed.
+    pub fn close_settlement_checkpoint(ctx: Context<CloseSettlementCheckpoint>) -> Result<()> {
+        let ob = &mut ctx.accounts.order_book;
+        require!(
+            ob.authority == ctx.accounts.payer.key() || ob.event_checkpoint.is_full(),
+            OrderBookError::Unauthorized
+        );
+        let closed = ob.event_checkpoint.close_epoch()?;
+        let order_book = ob.key();
+
+        let checkpoint = &mut ctx.accounts.settlement_checkpoint;
+        checkpoint.order_book = order_book;
+        checkpoint.root = closed;
+        checkpoint.bump = ctx.bumps.settlement_checkpoint;
+
+        emit_event!(ctx, EventEpochClosed {
+            checkpoint: order_book,
+            epoch: closed.epoch,
+            root: closed.root,
+            event_count: closed.event_count,
+        });
+
+        Ok(())
+    }
+
+    /// Check that `settlement` was recorded in checkpoint `epoch` at
+    /// `proof.leaf_index`. Closed epochs need their `SettlementCheckpoint`.
+    pub fn verify_settlement(
+        ctx: Context<VerifySettlement>,
+        epoch: u64,
+        settlement: OrderSettled,
+        proof: MerkleProof,
+    ) -> Result<()> {
+        let ob = &ctx.accounts.order_book;
+        require_keys_eq!(settlement.order_book, ob.key(), OrderBookError::InvalidSettlementProof);
+        let is_valid = match &ctx.accounts.settlement_checkpoint {
+            Some(closed) => {
+                closed.root.epoch == epoch && closed.root.verify(settlement.compute_hash()?, &proof)
+            }
+            None => ob.event_checkpoint.verify_event(epoch, &settlement, &proof)?,
+        };
+        require!(is_valid, OrderBookError::InvalidSettlementProof);
+        Ok(())
+    }
+
     /// Reclaim settlement receipt rent after expiry
     pub fn cleanup_settlement(ctx: Context<CleanupSettlement>) -> Result<()> {
         let receipt = &ctx.accounts.settlement_receipt;
-        let clock = Clock::get()?;
+        let now = receipt.expiry.unit.now()?;
 
         let cleanup_time = receipt
             .expiry
@@ -568,35 +672,33 @@ pub mod stratum_orderbook {
             .ok_or(OrderBookError::Overflow)?;
 
         require!(
-            receipt.expiry.expires_at > 0 && clock.unix_timestamp > cleanup_time,
+            receipt.expiry.expires_at > 0 && now > cleanup_time,
             OrderBookError::SettlementNotExpired
         );
 
-        // Dynamic reward: base escalates over 24h up to 10x
-        let base_reward = receipt.expiry.cleanup_reward;
-        let overdue = clock.unix_timestamp.saturating_sub(cleanup_time).max(0);
-        let escalation_period: i64 = 86400;
-        let max_multiplier: u64 = 10;
-
-        let reward = if escalation_period <= 0 || overdue <= 0 {
-            base_reward
-        } else {
-            let factor = if overdue >= escalation_period {
-                max_multiplier
-            } else {
-                1 + ((max_multiplier - 1) * overdue as u64) / escalation_period as u64
-            };
-            base_reward.saturating_mul(factor)
-        };
+        // Dynamic reward, following the order book's curve
+        let ob = &ctx.accounts.order_book;
+        let reward = ob
+            .cleanup_reward_config(&receipt.expiry)
+            .calculate_dynamic_reward_at(now)?;
 
-        let available = ctx.accounts.settlement_receipt.to_account_info().lamports();
-        let capped_reward = reward.min(available.saturating_sub(890880));
-        if capped_reward > 0 {
-            **ctx.accounts.settlement_receipt.to_account_info().try_borrow_mut_lamports()? -= capped_reward;
-            **ctx.accounts.cleaner.to_account_info().try_borrow_mut_lamports()? += capped_reward;
-        }
+        let ob_seeds = &[
+            OrderBook::SEED_PREFIX,
+            ob.authority.as_ref(),
+            ob.base_mint.as_ref(),
+            ob.quote_mint.as_ref(),
+            &[ob.bump],
+        ];
+        let capped_reward = pay_cleanup_reward(
+            ctx.accounts.stratum_program.to_account_info(),
+            ctx.accounts.reward_pool.to_account_info(),
+            ctx.accounts.order_book.to_account_info(),
+            ctx.accounts.cleaner.to_account_info(),
+            reward,
+            &[&ob_seeds[..]],
+        )?;
 
-        emit!(SettlementCleaned {
+        emit_event!(ctx, SettlementCleaned {
             settlement: ctx.accounts.settlement_receipt.key(),
             cleaner: ctx.accounts.cleaner.key(),
             reward: capped_reward,
@@ -627,6 +729,50 @@ pub mod stratum_orderbook {
         Ok(())
     }
 
+    /// Migrate an order book created before the Stratum accumulators,
+    /// `expiry_unit`, `cleanup_curve` and the recent-trades ring to the
+    /// current layout. Migrate the book before its epochs and receipts.
+    /// Only the order book authority can call this; current books are left
+    /// unchanged.
+    pub fn migrate_order_book(ctx: Context<MigrateOrderBook>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+
+        migrate_account(
+            &ctx.accounts.order_book.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            OrderBook::LEGACY_SPACE,
+            OrderBook::SPACE,
+            |legacy: LegacyOrderBook| {
+                require_keys_eq!(legacy.authority, authority, OrderBookError::Unauthorized);
+                Ok(OrderBook::from(legacy))
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Migrate a settlement receipt created before `ExpiryConfig::unit` to
+    /// the current layout. Only the order book authority can call this;
+    /// current receipts are left unchanged.
+    pub fn migrate_settlement_receipt(ctx: Context<MigrateSettlementReceipt>) -> Result<()> {
+        let order_book = ctx.accounts.order_book.key();
+
+        migrate_account(
+            &ctx.accounts.receipt.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            SettlementReceipt::LEGACY_SPACE,
+            SettlementReceipt::SPACE,
+            |legacy: LegacySettlementReceipt| {
+                require_keys_eq!(legacy.order_book, order_book, OrderBookError::Unauthorized);
+                Ok(SettlementReceipt::from(legacy))
+            },
+        )?;
+
+        Ok(())
+    }
+
     // =========================================================================
     // Decentralized Cranker Instructions
     // =========================================================================
@@ -758,12 +904,17 @@ pub mod stratum_orderbook {
         epoch.order_count = order_count;
         epoch.root_submitted = true;
         epoch.submitted_by = ctx.accounts.cranker.key();
-        epoch.challenge_deadline = clock.unix_timestamp + registry.challenge_period;
+        epoch.challenge_deadline = ctx
+            .accounts
+            .order_book
+            .expiry_unit
+            .current(&clock)
+            .saturating_add(registry.challenge_period);
 
         let ob = &mut ctx.accounts.order_book;
         ob.total_orders = ob.total_orders.saturating_add(order_count as u64);
 
-        emit!(EpochRootSubmitted {
+        emit_event!(ctx, EpochRootSubmitted {
             epoch: epoch.key(),
             merkle_root: root,
             order_count,
@@ -785,7 +936,7 @@ pub mod stratum_orderbook {
         require!(epoch.root_submitted, OrderBookError::EpochNotFinalized);
         require!(!epoch.is_finalized, OrderBookError::EpochAlreadyFinalized);
         require!(
-            clock.unix_timestamp <= epoch.challenge_deadline,
+            ctx.accounts.order_book.expiry_unit.current(&clock) <= epoch.challenge_deadline,
             OrderBookError::ChallengeDeadlineExpired
         );
 
@@ -915,10 +1066,39 @@ fn spl_transfer<'info>(
     Ok(())
 }
 
+/// Pay a cleanup reward from a stratum reward pool, signed by the order book PDA.
+/// Returns the amount actually paid.
+fn pay_cleanup_reward<'info>(
+    stratum_program: AccountInfo<'info>,
+    reward_pool: AccountInfo<'info>,
+    order_book: AccountInfo<'info>,
+    recipient: AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    let paid = stratum::cpi::pay_cleanup_reward(
+        CpiContext::new_with_signer(
+            stratum_program,
+            stratum::cpi::accounts::PayCleanupReward {
+                reward_pool,
+                consumer: order_book,
+                recipient,
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+    Ok(paid.get())
+}
+
 // =============================================================================
 // Account Contexts
 // =============================================================================
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct CreateOrderBook<'info> {
     #[account(
@@ -966,6 +1146,7 @@ pub struct CreateOrderBook<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct CreateEpoch<'info> {
     #[account(
@@ -1000,6 +1181,36 @@ pub struct CreateEpoch<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct CreateRecentTrades<'info> {
+    #[account(
+        mut,
+        seeds = [
+            OrderBook::SEED_PREFIX,
+            order_book.authority.as_ref(),
+            order_book.base_mint.as_ref(),
+            order_book.quote_mint.as_ref()
+        ],
+        bump = order_book.bump,
+        constraint = order_book.authority == authority.key() @ OrderBookError::Unauthorized
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = RecentTrades::SPACE,
+        seeds = [RecentTrades::SEED_PREFIX, order_book.key().as_ref()],
+        bump
+    )]
+    pub recent_trades: AccountLoader<'info, RecentTrades>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 #[instruction(chunk_index: u32)]
 pub struct CreateOrderChunk<'info> {
@@ -1045,6 +1256,7 @@ pub struct CreateOrderChunk<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct SubmitEpochRoot<'info> {
     #[account(
@@ -1075,6 +1287,7 @@ pub struct SubmitEpochRoot<'info> {
     pub authority: Signer<'info>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct FinalizeEpoch<'info> {
     #[account(
@@ -1104,6 +1317,7 @@ pub struct FinalizeEpoch<'info> {
     pub authority: Signer<'info>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 #[instruction(
     maker_order: OrderLeaf,
@@ -1123,7 +1337,9 @@ pub struct SettleMatch<'info> {
             order_book.base_mint.as_ref(),
             order_book.quote_mint.as_ref()
         ],
-        bump = order_book.bump
+        bump = order_book.bump,
+        constraint = !order_book.has_recent_trades || recent_trades.is_some()
+            @ OrderBookError::MissingRecentTrades
     )]
     pub order_book: Box<Account<'info, OrderBook>>,
 
@@ -1231,8 +1447,17 @@ pub struct SettleMatch<'info> {
     pub token_program: AccountInfo<'info>,
 
     pub system_program: Program<'info, System>,
+
+    /// Recent-trades ring; required once created for this order book
+    #[account(
+        mut,
+        seeds = [RecentTrades::SEED_PREFIX, order_book.key().as_ref()],
+        bump
+    )]
+    pub recent_trades: Option<AccountLoader<'info, RecentTrades>>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 #[instruction(order: OrderLeaf, proof: Vec<[u8; 32]>, index: u32)]
 pub struct CancelOrder<'info> {
@@ -1297,6 +1522,7 @@ pub struct CancelOrder<'info> {
     pub token_program: AccountInfo<'info>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 #[instruction(order: OrderLeaf, proof: Vec<[u8; 32]>, index: u32)]
 pub struct CleanupExpiredOrder<'info> {
@@ -1351,6 +1577,15 @@ pub struct CleanupExpiredOrder<'info> {
     )]
     pub quote_vault: AccountInfo<'info>,
 
+    /// Reward pool funding cleanup rewards for this order book
+    #[account(
+        mut,
+        constraint = reward_pool.consumer == order_book.key() @ OrderBookError::Unauthorized
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub stratum_program: Program<'info, Stratum>,
+
     /// CHECK: Maker's refund account
     #[account(mut)]
     pub maker_refund_account: AccountInfo<'info>,
@@ -1364,6 +1599,81 @@ pub struct CleanupExpiredOrder<'info> {
     pub token_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct GetPriceQuote<'info> {
+    #[account(
+        seeds = [
+            OrderBook::SEED_PREFIX,
+            order_book.authority.as_ref(),
+            order_book.base_mint.as_ref(),
+            order_book.quote_mint.as_ref()
+        ],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CloseSettlementCheckpoint<'info> {
+    #[account(
+        mut,
+        seeds = [
+            OrderBook::SEED_PREFIX,
+            order_book.authority.as_ref(),
+            order_book.base_mint.as_ref(),
+            order_book.quote_mint.as_ref()
+        ],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = SettlementCheckpoint::SPACE,
+        seeds = [
+            SettlementCheckpoint::SEED_PREFIX,
+            order_book.key().as_ref(),
+            &order_book.event_checkpoint.epoch.to_le_bytes()
+        ],
+        bump
+    )]
+    pub settlement_checkpoint: Account<'info, SettlementCheckpoint>,
+
+    /// Order book authority, or anyone once the epoch is full
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifySettlement<'info> {
+    #[account(
+        seeds = [
+            OrderBook::SEED_PREFIX,
+            order_book.authority.as_ref(),
+            order_book.base_mint.as_ref(),
+            order_book.quote_mint.as_ref()
+        ],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    /// Root of the epoch being checked, if it is closed
+    #[account(
+        seeds = [
+            SettlementCheckpoint::SEED_PREFIX,
+            order_book.key().as_ref(),
+            &settlement_checkpoint.root.epoch.to_le_bytes()
+        ],
+        bump = settlement_checkpoint.bump
+    )]
+    pub settlement_checkpoint: Option<Account<'info, SettlementCheckpoint>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct CleanupSettlement<'info> {
     #[account(
@@ -1372,6 +1682,27 @@ pub struct CleanupSettlement<'info> {
     )]
     pub settlement_receipt: Account<'info, SettlementReceipt>,
 
+    #[account(
+        seeds = [
+            OrderBook::SEED_PREFIX,
+            order_book.authority.as_ref(),
+            order_book.base_mint.as_ref(),
+            order_book.quote_mint.as_ref()
+        ],
+        bump = order_book.bump,
+        constraint = settlement_receipt.order_book == order_book.key() @ OrderBookError::Unauthorized
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    /// Reward pool funding cleanup rewards for this order book
+    #[account(
+        mut,
+        constraint = reward_pool.consumer == order_book.key() @ OrderBookError::Unauthorized
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub stratum_program: Program<'info, Stratum>,
+
     /// Anyone can call cleanup
     #[account(mut)]
     pub cleaner: Signer<'info>,
@@ -1416,6 +1747,42 @@ pub struct MigrateEpoch<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateOrderBook<'info> {
+    /// CHECK: Owner, discriminator and layout are checked by `migrate_account`
+    #[account(mut)]
+    pub order_book: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateSettlementReceipt<'info> {
+    #[account(
+        seeds = [
+            OrderBook::SEED_PREFIX,
+            order_book.authority.as_ref(),
+            order_book.base_mint.as_ref(),
+            order_book.quote_mint.as_ref()
+        ],
+        bump = order_book.bump,
+        constraint = order_book.authority == authority.key() @ OrderBookError::Unauthorized
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    /// CHECK: Owner, discriminator and layout are checked by `migrate_account`
+    #[account(mut)]
+    pub receipt: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 // =============================================================================
 // Decentralized Cranker Account Contexts
 // =============================================================================
@@ -1516,6 +1883,7 @@ pub struct WithdrawStake<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct SubmitEpochRootDecentralized<'info> {
     #[account(
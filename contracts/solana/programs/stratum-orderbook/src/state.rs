@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
-use stratum::events::HistorySummary;
-use stratum::expiry::ExpiryConfig;
+use stratum::bitset::FixedBitSet;
+use stratum::checkpoint::{EpochRoot, EventAccumulator};
+use stratum::events::{DistributionSummary, EventRing, HistorySummary, PriceAccumulator};
+use stratum::dynamic_expiry::{DynamicExpiryConfig, RewardCurve};
+use stratum::expiry::{ExpiryConfig, ExpiryUnit};
+use stratum::migrate::{LegacyExpiryConfig, LegacyHistorySummary};
 
 /// Order side enum
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -62,6 +66,24 @@ pub struct OrderBook {
 
     /// Quote vault bump
     pub quote_vault_bump: u8,
+
+    /// Fill size distribution of settlements (from Stratum)
+    pub fill_distribution: DistributionSummary,
+
+    /// Whether the recent-trades ring exists (then `settle_match` requires it)
+    pub has_recent_trades: bool,
+
+    /// Unit for `OrderLeaf::expires_at` and epoch challenge deadlines
+    pub expiry_unit: ExpiryUnit,
+
+    /// Reward curve for expired order and settlement cleanup
+    pub cleanup_curve: RewardCurve,
+
+    /// Cumulative fill prices for TWAP/VWAP queries (from Stratum)
+    pub price_oracle: PriceAccumulator,
+
+    /// Merkle accumulator over settlements, for proving past fills (from Stratum)
+    pub event_checkpoint: EventAccumulator,
 }
 
 impl OrderBook {
@@ -77,14 +99,155 @@ impl OrderBook {
         8 +  // tick_size
         2 +  // fee_bps
         32 + // fee_vault
-        (8 + 16 + 8 + 8 + 8 + 8 + 32) + // history (HistorySummary)
-        (8 + 8 + 8 + 8) + // settlement_expiry (ExpiryConfig)
+        (8 + 16 + 8 + 8 + 8 + 8 + 32 + 8) + // history (HistorySummary)
+        (8 + 8 + 8 + 8 + 1) + // settlement_expiry (ExpiryConfig)
+        1 +  // is_active
+        1 +  // bump
+        1 +  // base_vault_bump
+        1 +  // quote_vault_bump
+        ((8 + 16 + 8 + 8 + 8 + 8 + 32 + 8) + 16 + 4 * 65) + // fill_distribution (DistributionSummary)
+        1 +  // has_recent_trades
+        1 +  // expiry_unit
+        (1 + 8) + // cleanup_curve (RewardCurve)
+        (8 + 8 + 56 + 56 * 8 + 1 + 1) + // price_oracle (PriceAccumulator)
+        (8 + 8 + 32 * 16); // event_checkpoint (EventAccumulator)
+
+    /// Size of a `LegacyOrderBook` account
+    pub const LEGACY_SPACE: usize = 8 + // discriminator
+        32 + // authority
+        32 + // base_mint
+        32 + // quote_mint
+        32 + // base_vault
+        32 + // quote_vault
+        4 +  // current_epoch
+        8 +  // total_orders
+        8 +  // total_settlements
+        8 +  // tick_size
+        2 +  // fee_bps
+        32 + // fee_vault
+        LegacyHistorySummary::SPACE + // history
+        LegacyExpiryConfig::SPACE + // settlement_expiry
         1 +  // is_active
         1 +  // bump
         1 +  // base_vault_bump
         1;   // quote_vault_bump
 
     pub const SEED_PREFIX: &'static [u8] = b"order_book";
+
+    /// Cleanup rewards escalate up to this multiple of the base reward
+    pub const CLEANUP_MAX_MULTIPLIER: u64 = 10;
+
+    /// Default curve: base reward stepping up to 10x over a day
+    pub const DEFAULT_CLEANUP_CURVE: RewardCurve = RewardCurve::Stepwise { steps: 9 };
+
+    /// Dynamic cleanup reward for an item with `expiry`: escalates from its
+    /// `cleanup_reward` to `CLEANUP_MAX_MULTIPLIER`x over about a day (in the
+    /// expiry's unit) along `cleanup_curve`
+    pub fn cleanup_reward_config(&self, expiry: &ExpiryConfig) -> DynamicExpiryConfig {
+        DynamicExpiryConfig::from_expiry(
+            expiry,
+            expiry.cleanup_reward.saturating_mul(Self::CLEANUP_MAX_MULTIPLIER),
+            expiry.unit.day(),
+            self.cleanup_curve,
+        )
+    }
+}
+
+/// `OrderBook` as laid out before the Stratum accumulators, expiry unit,
+/// cleanup curve and recent-trades ring were added
+///
+/// Migrated books measure order expiry in unix timestamps, as they did.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyOrderBook {
+    pub authority: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub current_epoch: u32,
+    pub total_orders: u64,
+    pub total_settlements: u64,
+    pub tick_size: u64,
+    pub fee_bps: u16,
+    pub fee_vault: Pubkey,
+    pub history: LegacyHistorySummary,
+    pub settlement_expiry: LegacyExpiryConfig,
+    pub is_active: bool,
+    pub bump: u8,
+    pub base_vault_bump: u8,
+    pub quote_vault_bump: u8,
+}
+
+impl From<LegacyOrderBook> for OrderBook {
+    fn from(legacy: LegacyOrderBook) -> Self {
+        Self {
+            authority: legacy.authority,
+            base_mint: legacy.base_mint,
+            quote_mint: legacy.quote_mint,
+            base_vault: legacy.base_vault,
+            quote_vault: legacy.quote_vault,
+            current_epoch: legacy.current_epoch,
+            total_orders: legacy.total_orders,
+            total_settlements: legacy.total_settlements,
+            tick_size: legacy.tick_size,
+            fee_bps: legacy.fee_bps,
+            fee_vault: legacy.fee_vault,
+            history: legacy.history.into(),
+            settlement_expiry: legacy.settlement_expiry.into(),
+            is_active: legacy.is_active,
+            bump: legacy.bump,
+            base_vault_bump: legacy.base_vault_bump,
+            quote_vault_bump: legacy.quote_vault_bump,
+            fill_distribution: DistributionSummary::default(),
+            has_recent_trades: false,
+            expiry_unit: ExpiryUnit::Timestamp,
+            cleanup_curve: OrderBook::DEFAULT_CLEANUP_CURVE,
+            price_oracle: PriceAccumulator::default(),
+            event_checkpoint: EventAccumulator::default(),
+        }
+    }
+}
+
+/// Last 64 settlements of an order book, for dashboards without an indexer.
+/// Zero-copy so appending a trade doesn't deserialize the whole ring.
+#[account(zero_copy)]
+pub struct RecentTrades {
+    /// Parent order book
+    pub order_book: Pubkey,
+
+    /// Recent `OrderSettled` records (actor = taker, value = fill amount)
+    pub ring: EventRing,
+}
+
+impl RecentTrades {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // order_book
+        EventRing::SPACE; // ring
+
+    pub const SEED_PREFIX: &'static [u8] = b"recent_trades";
+}
+
+/// Root of one closed settlement checkpoint epoch, so every closed epoch
+/// stays provable with `verify_settlement`
+#[account]
+pub struct SettlementCheckpoint {
+    /// Parent order book
+    pub order_book: Pubkey,
+
+    /// The closed epoch's root
+    pub root: EpochRoot,
+
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl SettlementCheckpoint {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // order_book
+        EpochRoot::INIT_SPACE + // root
+        1;   // bump
+
+    pub const SEED_PREFIX: &'static [u8] = b"settlement_checkpoint";
 }
 
 /// An epoch containing a batch of orders committed via merkle root
@@ -117,7 +280,8 @@ pub struct Epoch {
     /// Which cranker submitted this root (Pubkey::default() for legacy single-authority)
     pub submitted_by: Pubkey,
 
-    /// Deadline after which epoch can be finalized (0 = no challenge period)
+    /// Deadline after which epoch can be finalized (0 = no challenge period),
+    /// in the order book's `expiry_unit`
     pub challenge_deadline: i64,
 
     /// PDA bump
@@ -142,7 +306,7 @@ impl Epoch {
 }
 
 /// Bitfield chunk tracking active/filled status of orders within an epoch.
-/// Wraps Stratum's BitfieldChunk concept but owns its own PDA.
+/// Uses Stratum's FixedBitSet semantics but owns its own PDA.
 /// bit set = active order, bit unset = filled/cancelled
 #[account]
 pub struct OrderChunk {
@@ -177,49 +341,32 @@ impl OrderChunk {
 
     /// Check if an order slot is active
     pub fn is_active(&self, index: u16) -> bool {
-        if index >= Self::BITS_PER_CHUNK as u16 {
-            return false;
-        }
-        let byte_idx = (index / 8) as usize;
-        let bit_idx = index % 8;
-        (self.bits[byte_idx] >> bit_idx) & 1 == 1
+        self.is_set(index)
     }
 
-    /// Set an order slot as active
-    pub fn set_active(&mut self, index: u16) -> bool {
-        if index >= Self::BITS_PER_CHUNK as u16 {
-            return false;
-        }
-        let byte_idx = (index / 8) as usize;
-        let bit_idx = index % 8;
-        let was_active = self.is_active(index);
-        if !was_active {
-            self.bits[byte_idx] |= 1 << bit_idx;
-            self.active_count = self.active_count.saturating_add(1);
-        }
-        !was_active
+    /// Unset an order slot (mark as filled/cancelled), returns Ok(true) if was active
+    pub fn set_inactive(&mut self, index: u16) -> Result<bool> {
+        self.unset(index)
     }
+}
 
-    /// Unset an order slot (mark as filled/cancelled)
-    pub fn set_inactive(&mut self, index: u16) -> bool {
-        if index >= Self::BITS_PER_CHUNK as u16 {
-            return false;
-        }
-        let byte_idx = (index / 8) as usize;
-        let bit_idx = index % 8;
-        let was_active = self.is_active(index);
-        if was_active {
-            self.bits[byte_idx] &= !(1 << bit_idx);
-            self.active_count = self.active_count.saturating_sub(1);
-        }
-        was_active
+impl FixedBitSet for OrderChunk {
+    const CAPACITY: u32 = Self::BITS_PER_CHUNK;
+
+    fn bits(&self) -> &[u8] {
+        &self.bits
+    }
+
+    fn bits_mut(&mut self) -> &mut [u8] {
+        &mut self.bits
     }
 
-    /// Split a global order index into (chunk_index, local_index)
-    pub fn split_index(global_index: u32) -> (u32, u16) {
-        let chunk = global_index / Self::BITS_PER_CHUNK;
-        let local = (global_index % Self::BITS_PER_CHUNK) as u16;
-        (chunk, local)
+    fn cached_count(&self) -> u16 {
+        self.active_count
+    }
+
+    fn cached_count_mut(&mut self) -> &mut u16 {
+        &mut self.active_count
     }
 }
 
@@ -270,13 +417,61 @@ impl SettlementReceipt {
         8 +  // fill_amount
         8 +  // fill_price
         8 +  // fee_paid
-        (8 + 8 + 8 + 8) + // expiry (ExpiryConfig)
+        (8 + 8 + 8 + 8 + 1) + // expiry (ExpiryConfig)
+        8 +  // settled_at
+        1;   // bump
+
+    /// Size of a `LegacySettlementReceipt` account
+    pub const LEGACY_SPACE: usize = 8 + // discriminator
+        32 + // order_book
+        32 + // maker
+        32 + // taker
+        8 +  // maker_order_id
+        8 +  // taker_order_id
+        8 +  // fill_amount
+        8 +  // fill_price
+        8 +  // fee_paid
+        LegacyExpiryConfig::SPACE + // expiry
         8 +  // settled_at
         1;   // bump
 
     pub const SEED_PREFIX: &'static [u8] = b"settlement";
 }
 
+/// `SettlementReceipt` as laid out before `ExpiryConfig::unit` was added
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacySettlementReceipt {
+    pub order_book: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub maker_order_id: u64,
+    pub taker_order_id: u64,
+    pub fill_amount: u64,
+    pub fill_price: u64,
+    pub fee_paid: u64,
+    pub expiry: LegacyExpiryConfig,
+    pub settled_at: i64,
+    pub bump: u8,
+}
+
+impl From<LegacySettlementReceipt> for SettlementReceipt {
+    fn from(legacy: LegacySettlementReceipt) -> Self {
+        Self {
+            order_book: legacy.order_book,
+            maker: legacy.maker,
+            taker: legacy.taker,
+            maker_order_id: legacy.maker_order_id,
+            taker_order_id: legacy.taker_order_id,
+            fill_amount: legacy.fill_amount,
+            fill_price: legacy.fill_price,
+            fee_paid: legacy.fee_paid,
+            expiry: legacy.expiry.into(),
+            settled_at: legacy.settled_at,
+            bump: legacy.bump,
+        }
+    }
+}
+
 /// Order leaf data — not stored on-chain.
 /// Serialized and hashed to create merkle tree leaves.
 /// Must match the TypeScript SDK's serialization exactly.
@@ -290,5 +485,32 @@ pub struct OrderLeaf {
     pub epoch_index: u32,
     pub order_index: u32,
     pub created_at: i64,
+    /// Expiry in the order book's `expiry_unit` (0 = never expires)
     pub expires_at: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_book_spaces() {
+        let legacy_data = vec![0u8; OrderBook::LEGACY_SPACE - 8];
+        let legacy = LegacyOrderBook::deserialize(&mut legacy_data.as_slice()).unwrap();
+        let mut order_book = OrderBook::from(legacy);
+        assert_eq!(order_book.expiry_unit, ExpiryUnit::Timestamp);
+        assert_eq!(order_book.history.sequence, 0);
+
+        // SPACE fits the largest curve
+        order_book.cleanup_curve = RewardCurve::Exponential { doubling_period: 0 };
+        assert_eq!(order_book.try_to_vec().unwrap().len() + 8, OrderBook::SPACE);
+    }
+
+    #[test]
+    fn test_settlement_receipt_spaces() {
+        let legacy_data = vec![0u8; SettlementReceipt::LEGACY_SPACE - 8];
+        let legacy = LegacySettlementReceipt::deserialize(&mut legacy_data.as_slice()).unwrap();
+        let receipt = SettlementReceipt::from(legacy);
+        assert_eq!(receipt.try_to_vec().unwrap().len() + 8, SettlementReceipt::SPACE);
+    }
+}
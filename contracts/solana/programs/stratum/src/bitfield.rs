@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::bitset::FixedBitSet;
 use crate::errors::StratumError;
 
 /// Compact bitfield chunk that tracks up to 2048 boolean flags
@@ -27,11 +28,18 @@ pub struct BitfieldChunk {
     #[max_len(256)]
     pub bits: Vec<u8>,
 
-    /// Count of set bits (for quick stats)
+    /// Count of set bits in the current generation (for quick stats)
     pub set_count: u16,
 
     /// Bump seed for PDA
     pub bump: u8,
+
+    /// Registry generation the bits belong to. Stale chunks read as empty
+    /// through `is_set_in` / `snapshot` and are cleared on their next write.
+    pub generation: u32,
+
+    /// Whether the chunk is frozen for archiving (no more modifications)
+    pub is_frozen: bool,
 }
 
 impl BitfieldChunk {
@@ -41,105 +49,187 @@ impl BitfieldChunk {
     /// Size of the bits array
     pub const BYTES_SIZE: usize = 256;
 
+    /// Size of a `LegacyBitfieldChunk` account
+    pub const LEGACY_SPACE: usize = 8 + 32 + 32 + 4 + (4 + Self::BYTES_SIZE) + 2 + 1;
+
     /// Initialize with all zeros
-    pub fn initialize(&mut self, authority: Pubkey, registry: Pubkey, chunk_index: u32, bump: u8) {
+    pub fn initialize(
+        &mut self,
+        authority: Pubkey,
+        registry: Pubkey,
+        chunk_index: u32,
+        generation: u32,
+        bump: u8,
+    ) {
         self.authority = authority;
         self.registry = registry;
         self.chunk_index = chunk_index;
         self.bits = vec![0u8; Self::BYTES_SIZE];
         self.set_count = 0;
         self.bump = bump;
+        self.generation = generation;
+        self.is_frozen = false;
+    }
+
+    /// Whether the bits belong to registry `generation`
+    pub fn is_current(&self, generation: u32) -> bool {
+        self.generation == generation
     }
 
-    /// Check if a bit is set
-    pub fn is_set(&self, index: u16) -> bool {
-        if index >= Self::BITS_PER_CHUNK as u16 {
+    /// Read a bit as of registry `generation`; stale chunks read as empty.
+    /// Use this instead of `is_set` wherever the chunk isn't synced first.
+    pub fn is_set_in(&self, generation: u32, index: u16) -> bool {
+        self.is_current(generation) && self.is_set(index)
+    }
+
+    /// Lazily clear the chunk if it belongs to an older registry generation.
+    /// Returns true if the chunk was reset.
+    /// Frozen chunks are never written, so their stale bits stay in place
+    /// but still read as empty.
+    pub fn sync_generation(&mut self, generation: u32) -> bool {
+        if self.generation == generation || self.is_frozen {
             return false;
         }
-        let byte_idx = (index / 8) as usize;
-        let bit_idx = index % 8;
+        self.bits = vec![0u8; Self::BYTES_SIZE];
+        self.set_count = 0;
+        self.generation = generation;
+        true
+    }
 
-        if byte_idx >= self.bits.len() {
-            return false;
+    /// Freeze the chunk so its bits can be snapshotted and archived
+    pub fn freeze(&mut self) -> Result<()> {
+        require!(!self.is_frozen, StratumError::ChunkFrozen);
+        self.is_frozen = true;
+        Ok(())
+    }
+
+    /// Snapshot of the bits as of registry `generation`, for archiving
+    /// (always BYTES_SIZE long, all zeros for stale chunks)
+    pub fn snapshot(&self, generation: u32) -> [u8; Self::BYTES_SIZE] {
+        let mut snapshot = [0u8; Self::BYTES_SIZE];
+        if !self.is_current(generation) {
+            return snapshot;
         }
+        let len = self.bits.len().min(Self::BYTES_SIZE);
+        snapshot[..len].copy_from_slice(&self.bits[..len]);
+        snapshot
+    }
 
-        (self.bits[byte_idx] >> bit_idx) & 1 == 1
+    /// Archive leaf data: the bits of the chunk's own generation followed by
+    /// that generation (little-endian), so stale frozen chunks keep their bits
+    pub fn archive_data(&self) -> Vec<u8> {
+        Self::archive_preimage(&self.snapshot(self.generation), self.generation)
     }
 
-    /// Set a bit, returns Ok(true) if newly set, Ok(false) if already set
-    pub fn set(&mut self, index: u16) -> Result<bool> {
-        require!(
-            (index as u32) < Self::BITS_PER_CHUNK,
-            StratumError::IndexOutOfBounds
-        );
+    /// Archive leaf data for a `snapshot` taken in `generation`
+    pub fn archive_preimage(snapshot: &[u8], generation: u32) -> Vec<u8> {
+        let mut data = snapshot.to_vec();
+        data.extend_from_slice(&generation.to_le_bytes());
+        data
+    }
 
-        let byte_idx = (index / 8) as usize;
-        let bit_idx = index % 8;
+    /// Get the global index for a local bit index
+    pub fn global_index(&self, local_index: u16) -> u32 {
+        self.chunk_index * Self::BITS_PER_CHUNK + local_index as u32
+    }
+}
+
+impl FixedBitSet for BitfieldChunk {
+    const CAPACITY: u32 = Self::BITS_PER_CHUNK;
 
+    fn bits(&self) -> &[u8] {
+        &self.bits
+    }
+
+    fn bits_mut(&mut self) -> &mut [u8] {
         // Ensure bits vec is properly sized
         if self.bits.len() < Self::BYTES_SIZE {
             self.bits.resize(Self::BYTES_SIZE, 0);
         }
+        &mut self.bits
+    }
 
-        let was_set = self.is_set(index);
-        if !was_set {
-            self.bits[byte_idx] |= 1 << bit_idx;
-            self.set_count = self.set_count.saturating_add(1);
-        }
+    fn cached_count(&self) -> u16 {
+        self.set_count
+    }
 
-        Ok(!was_set)
+    fn cached_count_mut(&mut self) -> &mut u16 {
+        &mut self.set_count
     }
 
-    /// Unset a bit, returns Ok(true) if was set, Ok(false) if already unset
-    pub fn unset(&mut self, index: u16) -> Result<bool> {
-        require!(
-            (index as u32) < Self::BITS_PER_CHUNK,
-            StratumError::IndexOutOfBounds
-        );
+    fn ensure_writable(&self) -> Result<()> {
+        require!(!self.is_frozen, StratumError::ChunkFrozen);
+        Ok(())
+    }
+}
 
-        let byte_idx = (index / 8) as usize;
-        let bit_idx = index % 8;
+/// `BitfieldChunk` as laid out before generations and freezing
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyBitfieldChunk {
+    pub authority: Pubkey,
+    pub registry: Pubkey,
+    pub chunk_index: u32,
+    pub bits: Vec<u8>,
+    pub set_count: u16,
+    pub bump: u8,
+}
 
-        let was_set = self.is_set(index);
-        if was_set {
-            self.bits[byte_idx] &= !(1 << bit_idx);
-            self.set_count = self.set_count.saturating_sub(1);
+impl From<LegacyBitfieldChunk> for BitfieldChunk {
+    fn from(legacy: LegacyBitfieldChunk) -> Self {
+        Self {
+            authority: legacy.authority,
+            registry: legacy.registry,
+            chunk_index: legacy.chunk_index,
+            bits: legacy.bits,
+            set_count: legacy.set_count,
+            bump: legacy.bump,
+            generation: 0,
+            is_frozen: false,
         }
-
-        Ok(was_set)
     }
+}
 
-    /// Get the global index for a local bit index
-    pub fn global_index(&self, local_index: u16) -> u32 {
-        self.chunk_index * Self::BITS_PER_CHUNK + local_index as u32
+#[cfg(test)]
+impl BitfieldChunk {
+    /// Empty generation-0 chunk owned by `registry`
+    pub(crate) fn new_chunk(registry: Pubkey, chunk_index: u32) -> Self {
+        let mut chunk = Self {
+            authority: Pubkey::default(),
+            registry: Pubkey::default(),
+            chunk_index: 0,
+            bits: vec![],
+            set_count: 0,
+            bump: 0,
+            generation: 0,
+            is_frozen: false,
+        };
+        chunk.initialize(registry, registry, chunk_index, 0, 0);
+        chunk
     }
+}
 
-    /// Convert global index to (chunk_index, local_index)
-    pub fn split_index(global_index: u32) -> (u32, u16) {
-        let chunk = global_index / Self::BITS_PER_CHUNK;
-        let local = (global_index % Self::BITS_PER_CHUNK) as u16;
-        (chunk, local)
-    }
+/// Tombstone left when a bitfield chunk is archived
+///
+/// Archiving closes the chunk; the tombstone keeps its PDA from being
+/// re-created with zeroed bits (which would re-open every claim it held)
+/// and points at the archive leaf holding its snapshot.
+#[account]
+#[derive(InitSpace)]
+pub struct ArchivedChunk {
+    /// Bitfield registry the chunk belonged to
+    pub registry: Pubkey,
 
-    /// Count total set bits (expensive, use set_count for cached value)
-    pub fn count_set(&self) -> u16 {
-        self.bits.iter().map(|b| b.count_ones() as u16).sum()
-    }
+    /// Index of the archived chunk
+    pub chunk_index: u32,
 
-    /// Check if all bits are set
-    pub fn is_full(&self) -> bool {
-        self.set_count >= Self::BITS_PER_CHUNK as u16
-    }
+    /// Archive registry holding the chunk's snapshot
+    pub archive_registry: Pubkey,
 
-    /// Check if all bits are unset
-    pub fn is_empty(&self) -> bool {
-        self.set_count == 0
-    }
+    /// Archive index of the snapshot leaf
+    pub archive_index: u64,
 
-    /// Get percentage of bits set (0-10000 for 0.00% - 100.00%)
-    pub fn fill_rate_bps(&self) -> u16 {
-        ((self.set_count as u32) * 10000 / Self::BITS_PER_CHUNK) as u16
-    }
+    /// Bump seed for PDA
+    pub bump: u8,
 }
 
 /// Registry to manage multiple bitfield chunks
@@ -155,18 +245,26 @@ pub struct BitfieldRegistry {
     /// Number of chunks created
     pub chunks_created: u32,
 
-    /// Total bits set across all chunks
+    /// Total bits set across all chunks in the current generation
     pub total_set: u64,
 
     /// Bump seed for PDA
     pub bump: u8,
+
+    /// Global index at or before the first free slot (allocation scan hint)
+    pub alloc_hint: u32,
+
+    /// Current generation (incrementing it logically clears every chunk)
+    pub generation: u32,
 }
 
 impl BitfieldRegistry {
+    /// Size of a `LegacyBitfieldRegistry` account
+    pub const LEGACY_SPACE: usize = 8 + 32 + 8 + 4 + 8 + 1;
+
     /// Calculate how many chunks needed for a given capacity
     pub fn chunks_needed(capacity: u64) -> u32 {
-        ((capacity + BitfieldChunk::BITS_PER_CHUNK as u64 - 1)
-            / BitfieldChunk::BITS_PER_CHUNK as u64) as u32
+        capacity.div_ceil(BitfieldChunk::BITS_PER_CHUNK as u64) as u32
     }
 
     /// Initialize the registry
@@ -176,6 +274,21 @@ impl BitfieldRegistry {
         self.chunks_created = 0;
         self.total_set = 0;
         self.bump = bump;
+        self.alloc_hint = 0;
+        self.generation = 0;
+    }
+
+    /// Start a new generation, logically clearing every chunk.
+    /// Returns the number of bits that were set in the previous generation.
+    pub fn roll_generation(&mut self) -> Result<u64> {
+        let previous_total = self.total_set;
+        self.generation = self
+            .generation
+            .checked_add(1)
+            .ok_or(StratumError::Overflow)?;
+        self.total_set = 0;
+        self.alloc_hint = 0;
+        Ok(previous_total)
     }
 
     /// Record that a bit was set
@@ -188,6 +301,49 @@ impl BitfieldRegistry {
         self.total_set = self.total_set.saturating_sub(1);
     }
 
+    /// Chunk and local index where the next allocation scan should start
+    pub fn alloc_start(&self) -> (u32, u16) {
+        BitfieldChunk::split_index(self.alloc_hint)
+    }
+
+    /// Claim the next free slot in `chunk`, starting from the hint when it
+    /// points into this chunk. Returns the allocated global index.
+    pub fn allocate(&mut self, chunk: &mut BitfieldChunk) -> Result<u32> {
+        chunk.sync_generation(self.generation);
+
+        let (hint_chunk, hint_local) = self.alloc_start();
+        let start = if hint_chunk == chunk.chunk_index { hint_local } else { 0 };
+
+        // Bits before the hint are only free if the hint was stale
+        let local = chunk
+            .find_first_unset(start)
+            .or_else(|| chunk.find_first_unset(0))
+            .ok_or(StratumError::NoFreeSlot)?;
+
+        let global = chunk.global_index(local);
+        require!(
+            (global as u64) < self.total_capacity,
+            StratumError::NoFreeSlot
+        );
+
+        chunk.set(local)?;
+        self.record_set();
+        // Only the hinted slot moves the hint; other chunks may still have
+        // free slots below this one
+        if hint_chunk == chunk.chunk_index && local == hint_local {
+            self.alloc_hint = global.saturating_add(1);
+        }
+
+        Ok(global)
+    }
+
+    /// Move the hint back when a slot below it is freed
+    pub fn release(&mut self, global_index: u32) {
+        if global_index < self.alloc_hint {
+            self.alloc_hint = global_index;
+        }
+    }
+
     /// Get fill rate in basis points
     pub fn fill_rate_bps(&self) -> u16 {
         if self.total_capacity == 0 {
@@ -197,20 +353,57 @@ impl BitfieldRegistry {
     }
 }
 
+/// `BitfieldRegistry` as laid out before the allocation hint and
+/// generations
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyBitfieldRegistry {
+    pub authority: Pubkey,
+    pub total_capacity: u64,
+    pub chunks_created: u32,
+    pub total_set: u64,
+    pub bump: u8,
+}
+
+impl From<LegacyBitfieldRegistry> for BitfieldRegistry {
+    fn from(legacy: LegacyBitfieldRegistry) -> Self {
+        Self {
+            authority: legacy.authority,
+            total_capacity: legacy.total_capacity,
+            chunks_created: legacy.chunks_created,
+            total_set: legacy.total_set,
+            bump: legacy.bump,
+            // Scanning from 0 finds the first free slot
+            alloc_hint: 0,
+            generation: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+impl BitfieldRegistry {
+    /// Empty generation-0 registry tracking `total_capacity` items
+    pub(crate) fn new_registry(total_capacity: u64) -> Self {
+        let mut registry = Self {
+            authority: Pubkey::default(),
+            total_capacity: 0,
+            chunks_created: 0,
+            total_set: 0,
+            bump: 0,
+            alloc_hint: 0,
+            generation: 0,
+        };
+        registry.initialize(Pubkey::default(), total_capacity, 0);
+        registry
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_bitfield_set_get() {
-        let mut chunk = BitfieldChunk {
-            authority: Pubkey::default(),
-            registry: Pubkey::default(),
-            chunk_index: 0,
-            bits: vec![0u8; 256],
-            set_count: 0,
-            bump: 0,
-        };
+        let mut chunk = BitfieldChunk::new_chunk(Pubkey::default(), 0);
 
         assert!(!chunk.is_set(0));
         assert!(!chunk.is_set(100));
@@ -228,6 +421,128 @@ mod tests {
         assert_eq!(chunk.set_count, 3);
     }
 
+    #[test]
+    fn test_allocate() {
+        let mut registry = BitfieldRegistry::new_registry(2050);
+        let mut chunk = BitfieldChunk::new_chunk(Pubkey::default(), 1);
+
+        // Hint points at chunk 0, so chunk 1 is scanned from the start
+        assert_eq!(registry.allocate(&mut chunk).unwrap(), 2048);
+        assert_eq!(registry.allocate(&mut chunk).unwrap(), 2049);
+        assert_eq!(registry.alloc_hint, 0);
+
+        // Capacity is exhausted
+        assert!(registry.allocate(&mut chunk).is_err());
+
+        // Freed slots are reused first
+        chunk.unset(0).unwrap();
+        registry.record_unset();
+        registry.release(2048);
+        assert_eq!(registry.allocate(&mut chunk).unwrap(), 2048);
+        assert_eq!(registry.total_set, 2);
+    }
+
+    #[test]
+    fn test_allocate_across_chunks() {
+        let mut registry = BitfieldRegistry::new_registry(4096);
+        let mut first = BitfieldChunk::new_chunk(Pubkey::default(), 0);
+        let mut second = BitfieldChunk::new_chunk(Pubkey::default(), 1);
+
+        // Allocating from another chunk leaves the hint on chunk 0
+        assert_eq!(registry.allocate(&mut second).unwrap(), 2048);
+        assert_eq!(registry.alloc_start(), (0, 0));
+        assert_eq!(registry.allocate(&mut first).unwrap(), 0);
+        assert_eq!(registry.allocate(&mut first).unwrap(), 1);
+        assert_eq!(registry.alloc_start(), (0, 2));
+
+        // The hint crosses into chunk 1 once chunk 0 is full
+        for _ in 2..2048 {
+            registry.allocate(&mut first).unwrap();
+        }
+        assert_eq!(registry.alloc_start(), (1, 0));
+        assert!(registry.allocate(&mut first).is_err());
+        assert_eq!(registry.allocate(&mut second).unwrap(), 2049);
+        assert_eq!(registry.total_set, 2050);
+    }
+
+    #[test]
+    fn test_generation_roll() {
+        let mut registry = BitfieldRegistry::new_registry(2048);
+        let mut chunk = BitfieldChunk::new_chunk(Pubkey::default(), 0);
+
+        assert_eq!(registry.allocate(&mut chunk).unwrap(), 0);
+        assert_eq!(registry.allocate(&mut chunk).unwrap(), 1);
+
+        assert_eq!(registry.roll_generation().unwrap(), 2);
+        assert_eq!(registry.generation, 1);
+        assert_eq!(registry.total_set, 0);
+
+        // Stale chunk reads as empty before it is synced
+        assert!(chunk.is_set(0) && !chunk.is_set_in(registry.generation, 0));
+
+        // and is reset when next touched
+        assert!(chunk.sync_generation(registry.generation));
+        assert!(!chunk.is_set(0));
+        assert_eq!(chunk.set_count, 0);
+        assert!(!chunk.sync_generation(registry.generation));
+
+        assert_eq!(registry.allocate(&mut chunk).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_frozen_chunk() {
+        let mut chunk = BitfieldChunk::new_chunk(Pubkey::default(), 0);
+
+        chunk.set(9).unwrap();
+        chunk.freeze().unwrap();
+
+        assert!(chunk.set(10).is_err());
+        assert!(chunk.unset(9).is_err());
+        assert!(chunk.freeze().is_err());
+
+        assert_eq!(chunk.snapshot(0)[1], 0b10);
+
+        // Generation rolls don't write to a frozen chunk, but its stale
+        // bits read as empty
+        assert!(!chunk.sync_generation(1));
+        assert_eq!(chunk.bits[1], 0b10);
+        assert!(chunk.is_set_in(0, 9));
+        assert!(!chunk.is_set_in(1, 9));
+        assert_eq!(chunk.snapshot(1), [0; BitfieldChunk::BYTES_SIZE]);
+    }
+
+    #[test]
+    fn test_migrate_legacy_layouts() {
+        let legacy = LegacyBitfieldRegistry {
+            authority: Pubkey::new_unique(),
+            total_capacity: 4096,
+            chunks_created: 2,
+            total_set: 3,
+            bump: 255,
+        };
+        assert_eq!(legacy.try_to_vec().unwrap().len() + 8, BitfieldRegistry::LEGACY_SPACE);
+        let registry = BitfieldRegistry::from(legacy);
+        assert_eq!(registry.try_to_vec().unwrap().len(), BitfieldRegistry::INIT_SPACE);
+        assert_eq!((registry.alloc_hint, registry.generation), (0, 0));
+
+        let mut bits = vec![0u8; BitfieldChunk::BYTES_SIZE];
+        bits[0] = 0b101;
+        let legacy = LegacyBitfieldChunk {
+            authority: Pubkey::new_unique(),
+            registry: Pubkey::new_unique(),
+            chunk_index: 1,
+            bits,
+            set_count: 2,
+            bump: 255,
+        };
+        assert_eq!(legacy.try_to_vec().unwrap().len() + 8, BitfieldChunk::LEGACY_SPACE);
+        let chunk = BitfieldChunk::from(legacy);
+        assert_eq!(chunk.try_to_vec().unwrap().len(), BitfieldChunk::INIT_SPACE);
+        assert!(chunk.is_set_in(registry.generation, 0));
+        assert!(chunk.is_set_in(registry.generation, 2));
+        assert!(!chunk.is_frozen);
+    }
+
     #[test]
     fn test_split_index() {
         assert_eq!(BitfieldChunk::split_index(0), (0, 0));
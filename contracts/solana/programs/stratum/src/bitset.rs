@@ -0,0 +1,269 @@
+use anchor_lang::prelude::*;
+use crate::errors::StratumError;
+
+/// Shared bit-set semantics for fixed-size bit storage
+///
+/// Implementors only expose their byte storage and cached set count;
+/// every bit operation comes from the provided methods so all chunk
+/// types behave identically:
+/// - `is_set` returns false for out-of-range indices
+/// - `set` / `unset` fail with `IndexOutOfBounds` for out-of-range indices
+/// - `set` returns true if the bit was newly set
+/// - `unset` returns true if the bit was previously set
+///
+/// Used by:
+/// - `BitfieldChunk` (stratum)
+/// - `OrderChunk` (stratum-orderbook)
+/// - `ClaimChunk` (airdrop-example)
+pub trait FixedBitSet {
+    /// Number of bits the set can hold
+    const CAPACITY: u32;
+
+    /// Backing bytes (bit `i` lives in byte `i / 8`, position `i % 8`)
+    fn bits(&self) -> &[u8];
+
+    /// Mutable backing bytes, at least `CAPACITY / 8` long
+    fn bits_mut(&mut self) -> &mut [u8];
+
+    /// Cached count of set bits
+    fn cached_count(&self) -> u16;
+
+    /// Mutable cached count of set bits
+    fn cached_count_mut(&mut self) -> &mut u16;
+
+    /// Hook to reject writes (e.g. frozen chunks)
+    fn ensure_writable(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check if a bit is set
+    fn is_set(&self, index: u16) -> bool {
+        if (index as u32) >= Self::CAPACITY {
+            return false;
+        }
+        let byte_idx = (index / 8) as usize;
+        let bit_idx = index % 8;
+
+        match self.bits().get(byte_idx) {
+            Some(byte) => (byte >> bit_idx) & 1 == 1,
+            None => false,
+        }
+    }
+
+    /// Set a bit, returns Ok(true) if newly set, Ok(false) if already set
+    fn set(&mut self, index: u16) -> Result<bool> {
+        self.ensure_writable()?;
+        require!(
+            (index as u32) < Self::CAPACITY,
+            StratumError::IndexOutOfBounds
+        );
+
+        if self.is_set(index) {
+            return Ok(false);
+        }
+
+        let byte_idx = (index / 8) as usize;
+        let bit_idx = index % 8;
+        self.bits_mut()[byte_idx] |= 1 << bit_idx;
+
+        let count = self.cached_count_mut();
+        *count = count.saturating_add(1);
+
+        Ok(true)
+    }
+
+    /// Unset a bit, returns Ok(true) if was set, Ok(false) if already unset
+    fn unset(&mut self, index: u16) -> Result<bool> {
+        self.ensure_writable()?;
+        require!(
+            (index as u32) < Self::CAPACITY,
+            StratumError::IndexOutOfBounds
+        );
+
+        if !self.is_set(index) {
+            return Ok(false);
+        }
+
+        let byte_idx = (index / 8) as usize;
+        let bit_idx = index % 8;
+        self.bits_mut()[byte_idx] &= !(1 << bit_idx);
+
+        let count = self.cached_count_mut();
+        *count = count.saturating_sub(1);
+
+        Ok(true)
+    }
+
+    /// Convert global index to (chunk_index, local_index)
+    fn split_index(global_index: u32) -> (u32, u16)
+    where
+        Self: Sized,
+    {
+        let chunk = global_index / Self::CAPACITY;
+        let local = (global_index % Self::CAPACITY) as u16;
+        (chunk, local)
+    }
+
+    /// Count total set bits (expensive, use the cached count instead)
+    fn count_set(&self) -> u16 {
+        self.bits().iter().map(|b| b.count_ones() as u16).sum()
+    }
+
+    /// Check if all bits are set
+    fn is_full(&self) -> bool {
+        self.cached_count() as u32 >= Self::CAPACITY
+    }
+
+    /// Check if all bits are unset
+    fn is_empty(&self) -> bool {
+        self.cached_count() == 0
+    }
+
+    /// Get percentage of bits set (0-10000 for 0.00% - 100.00%)
+    fn fill_rate_bps(&self) -> u16 {
+        ((self.cached_count() as u32) * 10000 / Self::CAPACITY) as u16
+    }
+
+    /// Find the first unset bit at or after `from`
+    fn find_first_unset(&self, from: u16) -> Option<u16> {
+        find_first(self, from, false)
+    }
+
+    /// Find the first set bit at or after `from`
+    fn find_first_set(&self, from: u16) -> Option<u16> {
+        find_first(self, from, true)
+    }
+}
+
+/// Scan for the first bit matching `value`, skipping whole bytes that can't match
+fn find_first<T: FixedBitSet + ?Sized>(set: &T, from: u16, value: bool) -> Option<u16> {
+    let skip = if value { 0x00 } else { 0xFF };
+    let mut index = from as u32;
+
+    while index < T::CAPACITY {
+        // Missing bytes read as unset
+        let byte = set.bits().get((index / 8) as usize).copied().unwrap_or(0);
+
+        if index % 8 == 0 && byte == skip {
+            index += 8;
+            continue;
+        }
+        if set.is_set(index as u16) == value {
+            return Some(index as u16);
+        }
+        index += 1;
+    }
+
+    None
+}
+
+/// Fixed-array bit set holding `N` bytes (`N * 8` bits)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BitArray<const N: usize> {
+    /// The actual bits
+    pub bits: [u8; N],
+
+    /// Count of set bits
+    pub set_count: u16,
+}
+
+impl<const N: usize> Default for BitArray<N> {
+    fn default() -> Self {
+        Self {
+            bits: [0u8; N],
+            set_count: 0,
+        }
+    }
+}
+
+impl<const N: usize> FixedBitSet for BitArray<N> {
+    const CAPACITY: u32 = (N * 8) as u32;
+
+    fn bits(&self) -> &[u8] {
+        &self.bits
+    }
+
+    fn bits_mut(&mut self) -> &mut [u8] {
+        &mut self.bits
+    }
+
+    fn cached_count(&self) -> u16 {
+        self.set_count
+    }
+
+    fn cached_count_mut(&mut self) -> &mut u16 {
+        &mut self.set_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Chunk = BitArray<256>;
+
+    #[test]
+    fn test_set_unset_semantics() {
+        let mut set = Chunk::default();
+
+        assert!(!set.is_set(0));
+        assert!(set.set(0).unwrap());
+        assert!(!set.set(0).unwrap());
+        assert!(set.set(2047).unwrap());
+        assert_eq!(set.cached_count(), 2);
+        assert_eq!(set.count_set(), 2);
+
+        assert!(set.unset(0).unwrap());
+        assert!(!set.unset(0).unwrap());
+        assert_eq!(set.cached_count(), 1);
+
+        // Out of range: reads are false, writes error
+        assert!(!set.is_set(2048));
+        assert!(set.set(2048).is_err());
+        assert!(set.unset(2048).is_err());
+        assert_eq!(set.cached_count(), 1);
+    }
+
+    #[test]
+    fn test_counts_and_fill() {
+        let mut set = BitArray::<2>::default();
+        assert!(set.is_empty());
+        assert_eq!(set.fill_rate_bps(), 0);
+
+        for i in 0..8 {
+            set.set(i).unwrap();
+        }
+        assert_eq!(set.fill_rate_bps(), 5000);
+
+        for i in 8..16 {
+            set.set(i).unwrap();
+        }
+        assert!(set.is_full());
+        assert_eq!(set.find_first_unset(0), None);
+        assert_eq!(set.fill_rate_bps(), 10000);
+    }
+
+    #[test]
+    fn test_find_first() {
+        let mut set = Chunk::default();
+        assert_eq!(set.find_first_unset(0), Some(0));
+        assert_eq!(set.find_first_set(0), None);
+
+        for i in 0..20 {
+            set.set(i).unwrap();
+        }
+        set.set(1000).unwrap();
+
+        assert_eq!(set.find_first_unset(5), Some(20));
+        assert_eq!(set.find_first_set(20), Some(1000));
+        assert_eq!(set.find_first_set(1001), None);
+    }
+
+    #[test]
+    fn test_split_index() {
+        assert_eq!(Chunk::split_index(0), (0, 0));
+        assert_eq!(Chunk::split_index(2047), (0, 2047));
+        assert_eq!(Chunk::split_index(2048), (1, 0));
+        assert_eq!(BitArray::<1>::split_index(17), (2, 1));
+    }
+}
@@ -0,0 +1,296 @@
+use anchor_lang::prelude::*;
+use crate::errors::StratumError;
+use crate::events::{ArchivableEvent, EventSink};
+use crate::merkle::{hash_leaf, hash_nodes, MerkleProof};
+
+/// Depth of the per-epoch event tree
+pub const CHECKPOINT_DEPTH: usize = 16;
+
+/// Seed prefix of a standalone checkpoint's closed epochs:
+/// `[CHECKPOINT_EPOCH_SEED, event_checkpoint, epoch (LE)]`
+pub const CHECKPOINT_EPOCH_SEED: &[u8] = b"checkpoint_epoch";
+
+/// Root of a closed checkpoint epoch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug, InitSpace)]
+pub struct EpochRoot {
+    /// Epoch number
+    pub epoch: u64,
+
+    /// Merkle root over the epoch's events
+    pub root: [u8; 32],
+
+    /// Number of events in the epoch
+    pub event_count: u64,
+}
+
+impl EpochRoot {
+    /// Check that `event_hash` was appended in this epoch at `proof.leaf_index`
+    pub fn verify(&self, event_hash: [u8; 32], proof: &MerkleProof) -> bool {
+        proof.siblings.len() == CHECKPOINT_DEPTH && proof.verify(self.root, hash_leaf(&event_hash))
+    }
+}
+
+/// Append-only merkle accumulator over event hashes
+///
+/// Keeps only the right-most frontier of an incremental merkle tree
+/// (deposit contract style), so appends touch at most `CHECKPOINT_DEPTH`
+/// hashes and the account size is fixed. Each epoch is its own tree;
+/// closing an epoch returns its root, which the owner persists in a
+/// per-epoch account (e.g. `CheckpointEpoch`) so every closed epoch stays
+/// provable. A full epoch rejects appends until it is closed.
+///
+/// Leaves are `hash_leaf(event_hash)` and missing leaves are zero, so
+/// proofs are regular `verify_proof` proofs with `CHECKPOINT_DEPTH`
+/// siblings. Build them off-chain with `checkpoint_proof`.
+///
+/// Use cases:
+/// - Proving a historical event (e.g. a settlement) after RPC log pruning
+/// - Light-client style audits of emitted events
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct EventAccumulator {
+    /// Current epoch number
+    pub epoch: u64,
+
+    /// Events appended in the current epoch
+    pub event_count: u64,
+
+    /// Left siblings on the path of the next leaf, one per level
+    pub frontier: [[u8; 32]; 16],
+}
+
+impl EventAccumulator {
+    /// Events per epoch before it must be closed
+    /// (one short of 2^depth, as in the incremental tree construction)
+    pub const MAX_EVENTS: u64 = (1 << CHECKPOINT_DEPTH) - 1;
+
+    /// Whether the current epoch can take no more events
+    pub fn is_full(&self) -> bool {
+        self.event_count >= Self::MAX_EVENTS
+    }
+
+    /// Append an event hash. Fails if the epoch is full.
+    /// Returns the (epoch, leaf index) the event was stored at.
+    pub fn append(&mut self, event_hash: [u8; 32]) -> Result<(u64, u64)> {
+        require!(!self.is_full(), StratumError::CheckpointEpochFull);
+
+        let index = self.event_count;
+        self.event_count += 1;
+
+        let mut node = hash_leaf(&event_hash);
+        let mut size = self.event_count;
+        for level in 0..CHECKPOINT_DEPTH {
+            if size & 1 == 1 {
+                self.frontier[level] = node;
+                break;
+            }
+            node = hash_nodes(&self.frontier[level], &node);
+            size >>= 1;
+        }
+
+        Ok((self.epoch, index))
+    }
+
+    /// Append an archivable event
+    pub fn append_event<E: ArchivableEvent>(&mut self, event: &E) -> Result<(u64, u64)> {
+        self.append(event.compute_hash()?)
+    }
+
+    /// Merkle root over the current epoch's events
+    pub fn current_root(&self) -> [u8; 32] {
+        let mut node = [0u8; 32];
+        let mut zero = [0u8; 32];
+        let mut size = self.event_count;
+        for level in 0..CHECKPOINT_DEPTH {
+            node = if size & 1 == 1 {
+                hash_nodes(&self.frontier[level], &node)
+            } else {
+                hash_nodes(&node, &zero)
+            };
+            zero = hash_nodes(&zero, &zero);
+            size >>= 1;
+        }
+        node
+    }
+
+    /// Close the current epoch, returning its root and starting a new tree.
+    /// Empty epochs can't be closed.
+    pub fn close_epoch(&mut self) -> Result<EpochRoot> {
+        require!(self.event_count > 0, StratumError::EmptyCheckpointEpoch);
+
+        let closed = EpochRoot {
+            epoch: self.epoch,
+            root: self.current_root(),
+            event_count: self.event_count,
+        };
+
+        self.epoch = self.epoch.saturating_add(1);
+        self.event_count = 0;
+        self.frontier = [[0u8; 32]; CHECKPOINT_DEPTH];
+
+        Ok(closed)
+    }
+
+    /// Check that `event_hash` was appended in the current `epoch` at
+    /// `proof.leaf_index`. Closed epochs are checked with `EpochRoot::verify`.
+    pub fn verify(&self, epoch: u64, event_hash: [u8; 32], proof: &MerkleProof) -> bool {
+        let current = EpochRoot {
+            epoch: self.epoch,
+            root: self.current_root(),
+            event_count: self.event_count,
+        };
+        epoch == self.epoch && current.verify(event_hash, proof)
+    }
+
+    /// Check that `event` was appended in `epoch` at `proof.leaf_index`
+    pub fn verify_event<E: ArchivableEvent>(
+        &self,
+        epoch: u64,
+        event: &E,
+        proof: &MerkleProof,
+    ) -> Result<bool> {
+        Ok(self.verify(epoch, event.compute_hash()?, proof))
+    }
+}
+
+impl EventSink for EventAccumulator {
+    fn record_event<E: ArchivableEvent + Discriminator>(
+        &mut self,
+        _event: &E,
+        event_hash: [u8; 32],
+    ) -> Result<()> {
+        self.append(event_hash)?;
+        Ok(())
+    }
+}
+
+/// Build a proof for `event_hashes[index]` within one epoch's events
+/// (in append order). Intended for indexers and clients.
+pub fn checkpoint_proof(event_hashes: &[[u8; 32]], index: u32) -> Option<MerkleProof> {
+    if index as usize >= event_hashes.len() {
+        return None;
+    }
+
+    let mut level: Vec<[u8; 32]> = event_hashes.iter().map(|h| hash_leaf(h)).collect();
+    let mut zero = [0u8; 32];
+    let mut idx = index as usize;
+    let mut siblings = Vec::with_capacity(CHECKPOINT_DEPTH);
+
+    for _ in 0..CHECKPOINT_DEPTH {
+        siblings.push(level.get(idx ^ 1).copied().unwrap_or(zero));
+        level = level
+            .chunks(2)
+            .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(&zero)))
+            .collect();
+        zero = hash_nodes(&zero, &zero);
+        idx /= 2;
+    }
+
+    Some(MerkleProof {
+        siblings,
+        leaf_index: index,
+    })
+}
+
+/// Standalone event checkpoint account
+///
+/// For programs that can't embed an `EventAccumulator` in their own state:
+/// the authority (usually a program PDA signing via CPI) records event
+/// hashes here and closes epochs into `CheckpointEpoch` accounts.
+#[account]
+#[derive(InitSpace)]
+pub struct EventCheckpoint {
+    /// Authority that can record events and close epochs
+    pub authority: Pubkey,
+
+    /// The accumulator
+    pub accumulator: EventAccumulator,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+/// Root of one closed epoch of an `EventCheckpoint`
+/// (PDA: `[CHECKPOINT_EPOCH_SEED, event_checkpoint, epoch (LE)]`)
+#[account]
+#[derive(InitSpace)]
+pub struct CheckpointEpoch {
+    /// Checkpoint the epoch belongs to
+    pub checkpoint: Pubkey,
+
+    /// The closed epoch's root
+    pub root: EpochRoot,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(n: u8) -> Vec<[u8; 32]> {
+        (0..n).map(|i| [i.wrapping_add(1); 32]).collect()
+    }
+
+    #[test]
+    fn test_proofs_against_incremental_root() {
+        let events = hashes(11);
+        let mut acc = EventAccumulator::default();
+        for (i, hash) in events.iter().enumerate() {
+            assert_eq!(acc.append(*hash).unwrap(), (0, i as u64));
+        }
+
+        for i in 0..events.len() {
+            let proof = checkpoint_proof(&events, i as u32).unwrap();
+            assert!(acc.verify(0, events[i], &proof));
+        }
+
+        // Wrong leaf, index or epoch fails
+        let proof = checkpoint_proof(&events, 3).unwrap();
+        assert!(!acc.verify(0, events[4], &proof));
+        assert!(!acc.verify(1, events[3], &proof));
+        let moved = MerkleProof { leaf_index: 4, ..proof };
+        assert!(!acc.verify(0, events[3], &moved));
+        assert!(checkpoint_proof(&events, 11).is_none());
+    }
+
+    #[test]
+    fn test_epoch_boundary() {
+        let first = hashes(5);
+        let mut acc = EventAccumulator::default();
+        for hash in &first {
+            acc.append(*hash).unwrap();
+        }
+        let root = acc.current_root();
+
+        let closed = acc.close_epoch().unwrap();
+        assert_eq!(closed, EpochRoot { epoch: 0, root, event_count: 5 });
+        assert_eq!(acc.epoch, 1);
+        assert_eq!(acc.event_count, 0);
+        assert_eq!(acc.current_root(), EventAccumulator::default().current_root());
+
+        // Closed epochs are proven against their persisted root
+        let proof = checkpoint_proof(&first, 2).unwrap();
+        assert!(closed.verify(first[2], &proof));
+        assert!(!closed.verify(first[3], &proof));
+        assert!(!acc.verify(0, first[2], &proof));
+
+        // Empty epochs can't be closed, so roots can't be churned
+        assert!(acc.close_epoch().is_err());
+        assert_eq!(acc.epoch, 1);
+    }
+
+    #[test]
+    fn test_full_epoch_rejects_appends() {
+        let mut acc = EventAccumulator {
+            event_count: EventAccumulator::MAX_EVENTS,
+            ..Default::default()
+        };
+        assert!(acc.is_full());
+        assert!(acc.append([7; 32]).is_err());
+
+        let closed = acc.close_epoch().unwrap();
+        assert_eq!(closed.event_count, EventAccumulator::MAX_EVENTS);
+        assert_eq!(acc.append([7; 32]).unwrap(), (1, 0));
+    }
+}
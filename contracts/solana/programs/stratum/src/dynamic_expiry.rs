@@ -1,9 +1,56 @@
 use anchor_lang::prelude::*;
 use crate::errors::StratumError;
+use crate::expiry::{ExpiryConfig, ExpiryUnit};
+
+/// Shape of the cleanup reward over the escalation period
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug, InitSpace)]
+pub enum RewardCurve {
+    /// Rises linearly from base_reward to max_reward
+    #[default]
+    Linear,
+    /// Doubles every `doubling_period`, capped at max_reward
+    Exponential { doubling_period: i64 },
+    /// Rises from base_reward to max_reward in `steps` equal jumps
+    Stepwise { steps: u8 },
+    /// Dutch auction: starts at max_reward and decays linearly to base_reward
+    DutchAuction,
+}
+
+impl RewardCurve {
+    /// Reward after `overdue` time into an escalation `period` (period > 0, overdue >= 0)
+    pub fn reward(&self, base: u64, max: u64, overdue: i64, period: i64) -> u64 {
+        let range = max.saturating_sub(base);
+        let elapsed = overdue.min(period) as u128;
+        let linear = ((range as u128 * elapsed) / period as u128) as u64;
+
+        let reward = match *self {
+            RewardCurve::Linear => base.saturating_add(linear),
+            RewardCurve::Exponential { doubling_period } => {
+                if overdue >= period {
+                    return max;
+                }
+                let doublings = overdue / doubling_period.max(1);
+                if doublings >= 64 {
+                    max
+                } else {
+                    base.saturating_mul(1u64 << doublings)
+                }
+            }
+            RewardCurve::Stepwise { steps } => {
+                let steps = steps.max(1) as u128;
+                let step = (elapsed * steps) / period as u128;
+                base.saturating_add(((range as u128 * step) / steps) as u64)
+            }
+            RewardCurve::DutchAuction => max.saturating_sub(linear),
+        };
+
+        reward.clamp(base, max)
+    }
+}
 
 /// Dynamic expiry configuration with time-based reward escalation.
-/// Replaces static cleanup_reward with a model where the reward
-/// increases linearly from base_reward to max_reward over the
+/// Replaces static cleanup_reward with a model where the reward moves
+/// between base_reward and max_reward along `curve` over the
 /// escalation_period after expiry + grace_period.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
 pub struct DynamicExpiryConfig {
@@ -22,8 +69,14 @@ pub struct DynamicExpiryConfig {
     /// Maximum cleanup reward (lamports) — reached after escalation_period
     pub max_reward: u64,
 
-    /// Time in seconds over which reward escalates from base to max
+    /// Time over which reward escalates from base to max
     pub escalation_period: i64,
+
+    /// Unit all times and periods are measured in
+    pub unit: ExpiryUnit,
+
+    /// Shape of the reward over the escalation period
+    pub curve: RewardCurve,
 }
 
 impl DynamicExpiryConfig {
@@ -35,7 +88,45 @@ impl DynamicExpiryConfig {
         max_reward: u64,
         escalation_period: i64,
     ) -> Result<Self> {
-        let now = Clock::get()?.unix_timestamp;
+        Self::new_at(
+            Clock::get()?.unix_timestamp,
+            ttl_seconds,
+            grace_period,
+            base_reward,
+            max_reward,
+            escalation_period,
+        )
+    }
+
+    /// Create a new dynamic expiry config with TTL, grace and escalation in slots
+    pub fn new_slots(
+        ttl_slots: i64,
+        grace_slots: i64,
+        base_reward: u64,
+        max_reward: u64,
+        escalation_slots: i64,
+    ) -> Result<Self> {
+        let mut config = Self::new_at(
+            ExpiryUnit::Slot.now()?,
+            ttl_slots,
+            grace_slots,
+            base_reward,
+            max_reward,
+            escalation_slots,
+        )?;
+        config.unit = ExpiryUnit::Slot;
+        Ok(config)
+    }
+
+    /// Create a new dynamic expiry config, starting at `now`
+    pub fn new_at(
+        now: i64,
+        ttl_seconds: i64,
+        grace_period: i64,
+        base_reward: u64,
+        max_reward: u64,
+        escalation_period: i64,
+    ) -> Result<Self> {
         Ok(Self {
             created_at: now,
             expires_at: if ttl_seconds > 0 {
@@ -47,24 +138,51 @@ impl DynamicExpiryConfig {
             base_reward,
             max_reward: max_reward.max(base_reward),
             escalation_period: escalation_period.max(1),
+            unit: ExpiryUnit::Timestamp,
+            curve: RewardCurve::Linear,
         })
     }
 
+    /// Build a dynamic config from a static one: `cleanup_reward` becomes the
+    /// base reward, escalating to `max_reward` along `curve`
+    pub fn from_expiry(
+        expiry: &ExpiryConfig,
+        max_reward: u64,
+        escalation_period: i64,
+        curve: RewardCurve,
+    ) -> Self {
+        Self {
+            created_at: expiry.created_at,
+            expires_at: expiry.expires_at,
+            grace_period: expiry.grace_period,
+            base_reward: expiry.cleanup_reward,
+            max_reward: max_reward.max(expiry.cleanup_reward),
+            escalation_period,
+            unit: expiry.unit,
+            curve,
+        }
+    }
+
     /// Check if the record has expired
     pub fn is_expired(&self) -> Result<bool> {
-        if self.expires_at == 0 {
-            return Ok(false);
-        }
-        let now = Clock::get()?.unix_timestamp;
-        Ok(now > self.expires_at)
+        Ok(self.is_expired_at(self.unit.now()?))
+    }
+
+    /// Check if the record has expired at `now` (in the config's unit)
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        self.expires_at != 0 && now > self.expires_at
     }
 
     /// Check if cleanup is allowed (expired + grace period passed)
     pub fn can_cleanup(&self) -> Result<bool> {
+        self.can_cleanup_at(self.unit.now()?)
+    }
+
+    /// Check if cleanup is allowed at `now` (expired + grace period passed)
+    pub fn can_cleanup_at(&self, now: i64) -> Result<bool> {
         if self.expires_at == 0 {
             return Ok(false);
         }
-        let now = Clock::get()?.unix_timestamp;
         let cleanup_time = self
             .expires_at
             .checked_add(self.grace_period)
@@ -73,10 +191,13 @@ impl DynamicExpiryConfig {
     }
 
     /// Calculate dynamic cleanup reward based on time elapsed since cleanup eligibility.
-    /// Linearly escalates from base_reward to max_reward over escalation_period.
+    /// Follows `curve` between base_reward and max_reward over escalation_period.
     pub fn calculate_dynamic_reward(&self) -> Result<u64> {
-        let now = Clock::get()?.unix_timestamp;
+        self.calculate_dynamic_reward_at(self.unit.now()?)
+    }
 
+    /// Calculate the dynamic cleanup reward at `now`
+    pub fn calculate_dynamic_reward_at(&self, now: i64) -> Result<u64> {
         if self.expires_at == 0 {
             return Ok(0);
         }
@@ -91,27 +212,28 @@ impl DynamicExpiryConfig {
         }
 
         let overdue = now.saturating_sub(cleanup_start);
+        let max_reward = self.max_reward.max(self.base_reward);
 
         if self.escalation_period <= 0 {
-            return Ok(self.max_reward);
+            return Ok(match self.curve {
+                RewardCurve::DutchAuction => self.base_reward,
+                _ => max_reward,
+            });
         }
 
-        // Linear interpolation: base + (max - base) * min(overdue / escalation, 1.0)
-        let reward_range = self.max_reward.saturating_sub(self.base_reward);
-
-        let bonus = if overdue >= self.escalation_period {
-            reward_range
-        } else {
-            // (reward_range * overdue) / escalation_period
-            ((reward_range as u128 * overdue as u128) / self.escalation_period as u128) as u64
-        };
-
-        Ok(self.base_reward.saturating_add(bonus).min(self.max_reward))
+        Ok(self
+            .curve
+            .reward(self.base_reward, max_reward, overdue, self.escalation_period))
     }
 
     /// Calculate reward with a cap (e.g., don't exceed account rent)
     pub fn calculate_capped_reward(&self, max_lamports: u64) -> Result<u64> {
-        let reward = self.calculate_dynamic_reward()?;
+        self.calculate_capped_reward_at(self.unit.now()?, max_lamports)
+    }
+
+    /// Calculate reward at `now` with a cap
+    pub fn calculate_capped_reward_at(&self, now: i64, max_lamports: u64) -> Result<u64> {
+        let reward = self.calculate_dynamic_reward_at(now)?;
         Ok(reward.min(max_lamports))
     }
 
@@ -135,35 +257,225 @@ pub trait DynamicExpirable {
         self.dynamic_expiry().is_expired()
     }
 
+    fn is_expired_at(&self, now: i64) -> bool {
+        self.dynamic_expiry().is_expired_at(now)
+    }
+
     fn can_cleanup(&self) -> Result<bool> {
         self.dynamic_expiry().can_cleanup()
     }
 
+    fn can_cleanup_at(&self, now: i64) -> Result<bool> {
+        self.dynamic_expiry().can_cleanup_at(now)
+    }
+
     fn cleanup_reward(&self) -> Result<u64> {
         self.dynamic_expiry().calculate_dynamic_reward()
     }
+
+    fn cleanup_reward_at(&self, now: i64) -> Result<u64> {
+        self.dynamic_expiry().calculate_dynamic_reward_at(now)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
-    #[test]
-    fn test_reward_escalation_math() {
-        // Test the pure math without Clock dependency
-        let config = DynamicExpiryConfig {
+    fn config(grace_period: i64, escalation_period: i64) -> DynamicExpiryConfig {
+        DynamicExpiryConfig {
             created_at: 1000,
             expires_at: 2000,
-            grace_period: 100,
+            grace_period,
             base_reward: 5000,
             max_reward: 50000,
-            escalation_period: 86400, // 24 hours
-        };
+            escalation_period,
+            unit: ExpiryUnit::Timestamp,
+            curve: RewardCurve::Linear,
+        }
+    }
+
+    #[test]
+    fn test_reward_escalation_math() {
+        // Cleanup starts at 2100, escalates over 900 seconds
+        let config = config(100, 900);
+
+        // (now, expected reward)
+        let cases = [
+            (0, 5000),      // before expiry: base
+            (2000, 5000),   // at expiry: base
+            (2100, 5000),   // end of grace: base
+            (2101, 5050),   // 1s overdue: +45000/900
+            (2550, 27500),  // halfway
+            (2999, 49950),  // 1s before max
+            (3000, 50000),  // fully escalated
+            (i64::MAX, 50000),
+        ];
+
+        for (now, expected) in cases {
+            assert_eq!(config.calculate_dynamic_reward_at(now).unwrap(), expected, "now {}", now);
+        }
+    }
+
+    #[test]
+    fn test_reward_edge_cases() {
+        // Zero grace: escalation starts right at expiry
+        let zero_grace = config(0, 1000);
+        assert_eq!(zero_grace.calculate_dynamic_reward_at(2000).unwrap(), 5000);
+        assert_eq!(zero_grace.calculate_dynamic_reward_at(2500).unwrap(), 27500);
+
+        // Non-positive escalation period jumps straight to max
+        assert_eq!(config(100, 0).calculate_dynamic_reward_at(2101).unwrap(), 50000);
+        assert_eq!(config(100, -5).calculate_dynamic_reward_at(2101).unwrap(), 50000);
+
+        // Never expires: no reward
+        let mut never = config(100, 1000);
+        never.expires_at = 0;
+        assert_eq!(never.calculate_dynamic_reward_at(i64::MAX).unwrap(), 0);
+        assert!(!never.can_cleanup_at(i64::MAX).unwrap());
 
-        // Verify max >= base invariant
-        assert!(config.max_reward >= config.base_reward);
+        // Grace overflow
+        let overflow = config(i64::MAX, 1000);
+        assert!(overflow.calculate_dynamic_reward_at(0).is_err());
+        assert!(overflow.can_cleanup_at(0).is_err());
 
-        // Verify escalation period is positive
-        assert!(config.escalation_period > 0);
+        // Cap
+        assert_eq!(config(0, 1).calculate_capped_reward_at(3000, 7000).unwrap(), 7000);
+    }
+
+    #[test]
+    fn test_new_at() {
+        let config = DynamicExpiryConfig::new_at(100, 50, 10, 9000, 1000, 0).unwrap();
+        assert_eq!(config.expires_at, 150);
+        // max is raised to base, escalation clamped to at least 1
+        assert_eq!(config.max_reward, 9000);
+        assert_eq!(config.escalation_period, 1);
+
+        // Zero TTL never expires
+        assert_eq!(DynamicExpiryConfig::new_at(100, 0, 10, 1, 2, 3).unwrap().expires_at, 0);
+        assert!(DynamicExpiryConfig::new_at(i64::MAX, 1, 0, 1, 2, 3).is_err());
+    }
+
+    #[test]
+    fn test_reward_curves() {
+        // Cleanup starts at 2100, escalates over 900 seconds
+        let with_curve = |curve| DynamicExpiryConfig { curve, ..config(100, 900) };
+
+        // (curve, [(now, expected reward)])
+        let cases = [
+            (
+                RewardCurve::Exponential { doubling_period: 300 },
+                [(2100, 5000), (2399, 5000), (2400, 10000), (2700, 20000), (2999, 20000), (3000, 50000)],
+            ),
+            (
+                RewardCurve::Stepwise { steps: 3 },
+                [(2100, 5000), (2399, 5000), (2400, 20000), (2700, 35000), (2999, 35000), (3000, 50000)],
+            ),
+            (
+                RewardCurve::DutchAuction,
+                [(2100, 5000), (2101, 49950), (2550, 27500), (2999, 5050), (3000, 5000), (9999, 5000)],
+            ),
+        ];
+
+        for (curve, points) in cases {
+            let config = with_curve(curve);
+            for (now, expected) in points {
+                assert_eq!(
+                    config.calculate_dynamic_reward_at(now).unwrap(),
+                    expected,
+                    "{:?} at {}",
+                    curve,
+                    now
+                );
+            }
+        }
+
+        // Degenerate parameters stay in bounds
+        let zero_steps = with_curve(RewardCurve::Stepwise { steps: 0 });
+        assert_eq!(zero_steps.calculate_dynamic_reward_at(2999).unwrap(), 5000);
+        let zero_doubling = with_curve(RewardCurve::Exponential { doubling_period: 0 });
+        assert_eq!(zero_doubling.calculate_dynamic_reward_at(2200).unwrap(), 50000);
+        assert_eq!(
+            DynamicExpiryConfig { curve: RewardCurve::DutchAuction, ..config(100, 0) }
+                .calculate_dynamic_reward_at(2101)
+                .unwrap(),
+            5000
+        );
+    }
+
+    #[test]
+    fn test_from_expiry() {
+        let expiry = ExpiryConfig::new_slots_at(10, 100, 5, 1000).unwrap();
+        let config = DynamicExpiryConfig::from_expiry(
+            &expiry,
+            10_000,
+            50,
+            RewardCurve::Stepwise { steps: 9 },
+        );
+
+        assert_eq!(config.expires_at, 110);
+        assert_eq!(config.unit, ExpiryUnit::Slot);
+        assert_eq!(config.calculate_dynamic_reward_at(115).unwrap(), 1000);
+        assert_eq!(config.calculate_dynamic_reward_at(140).unwrap(), 5000);
+        assert_eq!(config.calculate_dynamic_reward_at(200).unwrap(), 10_000);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_every_curve_bounded(
+            escalation in any::<i64>(),
+            base in any::<u64>(),
+            max in any::<u64>(),
+            now in any::<i64>(),
+            param in any::<i64>(),
+        ) {
+            let curves = [
+                RewardCurve::Linear,
+                RewardCurve::Exponential { doubling_period: param },
+                RewardCurve::Stepwise { steps: param as u8 },
+                RewardCurve::DutchAuction,
+            ];
+            for curve in curves {
+                let config = DynamicExpiryConfig {
+                    base_reward: base,
+                    max_reward: max,
+                    escalation_period: escalation,
+                    curve,
+                    ..config(0, 0)
+                };
+                let reward = config.calculate_dynamic_reward_at(now).unwrap();
+                prop_assert!(reward >= base && reward <= max.max(base));
+            }
+        }
+
+        #[test]
+        fn prop_reward_bounded_and_monotonic(
+            grace in 0i64..=86400,
+            escalation in any::<i64>(),
+            base in any::<u64>(),
+            max in any::<u64>(),
+            now in any::<i64>(),
+            later in any::<i64>(),
+        ) {
+            let config = DynamicExpiryConfig {
+                created_at: 0,
+                expires_at: 1_000_000,
+                grace_period: grace,
+                base_reward: base,
+                max_reward: max.max(base),
+                escalation_period: escalation,
+                unit: ExpiryUnit::Slot,
+                curve: RewardCurve::Linear,
+            };
+            let (earlier, later) = (now.min(later), now.max(later));
+
+            let a = config.calculate_dynamic_reward_at(earlier).unwrap();
+            let b = config.calculate_dynamic_reward_at(later).unwrap();
+
+            prop_assert!(a >= config.base_reward && a <= config.max_reward);
+            prop_assert!(b >= config.base_reward && b <= config.max_reward);
+            prop_assert!(a <= b);
+        }
     }
 }
@@ -51,4 +51,57 @@ pub enum StratumError {
 
     #[msg("Arithmetic overflow")]
     Overflow,
+
+    // New variants are appended below so existing error codes stay stable
+
+    // State field errors
+    #[msg("Current state does not match expected state")]
+    StateMismatch,
+
+    #[msg("State value does not fit in entry width")]
+    InvalidState,
+
+    // Bitfield errors
+    #[msg("No free slot available")]
+    NoFreeSlot,
+
+    #[msg("Bitfield chunk is frozen")]
+    ChunkFrozen,
+
+    #[msg("Bitfield chunk must be frozen first")]
+    ChunkNotFrozen,
+
+    // Expiry errors
+    #[msg("Reward pool balance too low")]
+    InsufficientPoolBalance,
+
+    #[msg("Expiry index is full")]
+    ExpiryIndexFull,
+
+    #[msg("Item is already in the expiry index")]
+    ItemAlreadyIndexed,
+
+    #[msg("Item is not in the expiry index")]
+    ItemNotIndexed,
+
+    #[msg("Record has no lease terms")]
+    NoLease,
+
+    #[msg("Renewal exceeds the lease's maximum term")]
+    LeaseTermExceeded,
+
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+
+    #[msg("Bitfield chunk was archived")]
+    ChunkArchived,
+
+    #[msg("Price history is shorter than the requested window")]
+    InsufficientPriceHistory,
+
+    #[msg("Checkpoint epoch is full; close it first")]
+    CheckpointEpochFull,
+
+    #[msg("Checkpoint epoch has no events")]
+    EmptyCheckpointEpoch,
 }
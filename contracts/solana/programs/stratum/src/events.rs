@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::errors::StratumError;
 
 /// Compact history summary stored on-chain
 ///
@@ -29,8 +30,11 @@ pub struct HistorySummary {
     /// Last event timestamp
     pub last_timestamp: i64,
 
-    /// Checksum/hash of last event for verification
+    /// Head of the event hash chain: H(prev || event_hash || sequence)
     pub last_event_hash: [u8; 32],
+
+    /// Number of events folded into the hash chain
+    pub sequence: u64,
 }
 
 impl HistorySummary {
@@ -63,11 +67,40 @@ impl HistorySummary {
         Ok(())
     }
 
-    /// Set the last event hash (for verification)
+    /// Overwrite the chain head (breaks chain verification; prefer `chain_event`)
     pub fn set_last_hash(&mut self, hash: [u8; 32]) {
         self.last_event_hash = hash;
     }
 
+    /// Fold an event hash into the chain, returning the new head
+    pub fn chain_event(&mut self, event_hash: [u8; 32]) -> [u8; 32] {
+        self.sequence = self.sequence.saturating_add(1);
+        self.last_event_hash = chain_hash(&self.last_event_hash, &event_hash, self.sequence);
+        self.last_event_hash
+    }
+
+    /// Check that replaying `events` from an empty chain reproduces the head.
+    /// Lets indexers prove they hold every event the summary has seen.
+    pub fn verify_chain<E: ArchivableEvent>(&self, events: &[E]) -> Result<bool> {
+        let hashes = events
+            .iter()
+            .map(|e| e.compute_hash())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.verify_chain_from([0u8; 32], 0, &hashes))
+    }
+
+    /// Check that extending a known (`head`, `sequence`) checkpoint with
+    /// `event_hashes` reproduces the current head and sequence
+    pub fn verify_chain_from(
+        &self,
+        head: [u8; 32],
+        sequence: u64,
+        event_hashes: &[[u8; 32]],
+    ) -> bool {
+        let (head, sequence) = replay_chain(head, sequence, event_hashes);
+        head == self.last_event_hash && sequence == self.sequence
+    }
+
     /// Get average value (0 if no events)
     pub fn average(&self) -> u64 {
         if self.total_count == 0 {
@@ -82,7 +115,163 @@ impl HistorySummary {
     }
 }
 
+/// History summary with distribution statistics
+///
+/// Extends `HistorySummary` (kept as-is so existing layouts don't change)
+/// with a sum of squares for variance and a log2 histogram for approximate
+/// percentiles.
+///
+/// Use cases:
+/// - Trade size distribution and volatility for UIs
+/// - Outlier detection without an indexer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct DistributionSummary {
+    /// Count, sum, min, max and event hash chain
+    pub summary: HistorySummary,
+
+    /// Sum of squared values (saturating)
+    pub sum_squares: u128,
+
+    /// Event counts per log2 bucket: bucket 0 holds 0, bucket k holds
+    /// values in [2^(k-1), 2^k)
+    pub histogram: [u32; 65],
+}
+
+impl Default for DistributionSummary {
+    fn default() -> Self {
+        Self {
+            summary: HistorySummary::default(),
+            sum_squares: 0,
+            histogram: [0; 65],
+        }
+    }
+}
+
+impl DistributionSummary {
+    /// Number of histogram buckets
+    pub const BUCKETS: usize = 65;
+
+    /// Histogram bucket for a value
+    pub fn bucket_of(value: u64) -> usize {
+        (u64::BITS - value.leading_zeros()) as usize
+    }
+
+    /// Largest value that falls in a bucket
+    pub fn bucket_upper_bound(bucket: usize) -> u64 {
+        match bucket {
+            0 => 0,
+            64.. => u64::MAX,
+            k => (1u64 << k) - 1,
+        }
+    }
+
+    /// Record a new event
+    pub fn record(&mut self, value: u64, slot: u64, timestamp: i64) {
+        self.summary.record(value, slot, timestamp);
+        self.sum_squares = self
+            .sum_squares
+            .saturating_add(value as u128 * value as u128);
+
+        let bucket = Self::bucket_of(value);
+        self.histogram[bucket] = self.histogram[bucket].saturating_add(1);
+    }
+
+    /// Record event with current clock
+    pub fn record_now(&mut self, value: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        self.record(value, clock.slot, clock.unix_timestamp);
+        Ok(())
+    }
+
+    /// Fold an event hash into the summary's hash chain
+    pub fn chain_event(&mut self, event_hash: [u8; 32]) -> [u8; 32] {
+        self.summary.chain_event(event_hash)
+    }
+
+    /// Get average value (0 if no events)
+    pub fn average(&self) -> u64 {
+        self.summary.average()
+    }
+
+    /// Population variance (0 if no events)
+    pub fn variance(&self) -> u128 {
+        let count = self.summary.total_count as u128;
+        if count == 0 {
+            return 0;
+        }
+        // With sum = mean * count + r, count * variance is
+        // sum_squares - mean^2 * count - 2 * mean * r - r^2 / count
+        let mean = self.summary.total_value / count;
+        let r = self.summary.total_value % count;
+        self.sum_squares
+            .saturating_sub(mean.saturating_mul(mean).saturating_mul(count))
+            .saturating_sub(mean.saturating_mul(r).saturating_mul(2))
+            .saturating_sub(r * r / count)
+            / count
+    }
+
+    /// Population standard deviation (0 if no events)
+    pub fn stddev(&self) -> u64 {
+        isqrt(self.variance()) as u64
+    }
+
+    /// Approximate percentile (`bps` in basis points, 5000 = median).
+    /// Returns the upper bound of the histogram bucket holding that rank,
+    /// clamped to the observed min/max, so it is within 2x of the true value.
+    pub fn percentile(&self, bps: u16) -> u64 {
+        let total: u64 = self.histogram.iter().map(|&c| c as u64).sum();
+        if total == 0 {
+            return 0;
+        }
+
+        let bps = bps.min(10000) as u64;
+        let rank = (total * bps).div_ceil(10000).max(1);
+
+        let mut seen = 0u64;
+        for (bucket, &count) in self.histogram.iter().enumerate() {
+            seen += count as u64;
+            if seen >= rank {
+                return Self::bucket_upper_bound(bucket)
+                    .clamp(self.summary.min_value, self.summary.max_value);
+            }
+        }
+        self.summary.max_value
+    }
+
+    /// Approximate median
+    pub fn p50(&self) -> u64 {
+        self.percentile(5000)
+    }
+
+    /// Approximate 90th percentile
+    pub fn p90(&self) -> u64 {
+        self.percentile(9000)
+    }
+
+    /// Approximate 99th percentile
+    pub fn p99(&self) -> u64 {
+        self.percentile(9900)
+    }
+}
+
+/// Integer square root (floor)
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x / 2 + (x & 1);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
 /// Rolling window summary for time-based stats
+///
+/// Only keeps the current and previous window. For sliding sums over
+/// several buckets use `SlidingWindow`.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
 pub struct RollingWindow {
     /// Window duration in seconds
@@ -107,23 +296,31 @@ pub struct RollingWindow {
 impl RollingWindow {
     /// Create a new rolling window
     pub fn new(window_seconds: i64) -> Result<Self> {
-        let now = Clock::get()?.unix_timestamp;
-        Ok(Self {
+        Ok(Self::new_at(Clock::get()?.unix_timestamp, window_seconds))
+    }
+
+    /// Create a new rolling window starting at `now`
+    pub fn new_at(now: i64, window_seconds: i64) -> Self {
+        Self {
             window_seconds,
             window_start: now,
             window_count: 0,
             window_value: 0,
             prev_window_count: 0,
             prev_window_value: 0,
-        })
+        }
     }
 
     /// Record an event, rolling window if needed
     pub fn record(&mut self, value: u64) -> Result<()> {
-        let now = Clock::get()?.unix_timestamp;
+        self.record_at(Clock::get()?.unix_timestamp, value);
+        Ok(())
+    }
 
+    /// Record an event at `now`, rolling window if needed
+    pub fn record_at(&mut self, now: i64, value: u64) {
         // Check if we need to roll the window
-        if now >= self.window_start + self.window_seconds {
+        if now >= self.window_start.saturating_add(self.window_seconds) {
             // Save current as previous
             self.prev_window_count = self.window_count;
             self.prev_window_value = self.window_value;
@@ -136,8 +333,6 @@ impl RollingWindow {
 
         self.window_count = self.window_count.saturating_add(1);
         self.window_value = self.window_value.saturating_add(value as u128);
-
-        Ok(())
     }
 
     /// Get current window average
@@ -159,6 +354,435 @@ impl RollingWindow {
     }
 }
 
+/// Ring buffer of `N` fixed-size time buckets for sliding-window stats
+///
+/// Unlike `RollingWindow`, old data ages out one bucket at a time, and
+/// gaps of several buckets clear exactly the buckets that were skipped.
+///
+/// Use cases:
+/// - 24 hourly buckets for a sliding daily volume (`HourlyWindow`)
+/// - Per-minute rate limits
+/// - Activity over arbitrary sub-spans (last 3 hours, yesterday's peak hour)
+///
+/// Buckets are aligned to multiples of `bucket_seconds`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SlidingWindow<const N: usize> {
+    /// Bucket duration in seconds
+    pub bucket_seconds: i64,
+
+    /// Id (`time / bucket_seconds`) of the newest bucket
+    pub head_bucket: i64,
+
+    /// Event count per bucket (indexed by bucket id mod N)
+    pub counts: [u64; N],
+
+    /// Value sum per bucket (indexed by bucket id mod N)
+    pub values: [u128; N],
+}
+
+/// 24 hourly buckets
+pub type HourlyWindow = SlidingWindow<24>;
+
+impl<const N: usize> SlidingWindow<N> {
+    /// Serialized size
+    pub const SPACE: usize = 8 + 8 + 8 * N + 16 * N;
+
+    /// Create a new sliding window
+    pub fn new(bucket_seconds: i64) -> Result<Self> {
+        Self::new_at(Clock::get()?.unix_timestamp, bucket_seconds)
+    }
+
+    /// Create a new sliding window whose newest bucket contains `now`
+    pub fn new_at(now: i64, bucket_seconds: i64) -> Result<Self> {
+        require!(bucket_seconds > 0 && N > 0, StratumError::InvalidConfig);
+        Ok(Self {
+            bucket_seconds,
+            head_bucket: now.div_euclid(bucket_seconds),
+            counts: [0; N],
+            values: [0; N],
+        })
+    }
+
+    /// Bucket id containing `time`
+    pub fn bucket_id(&self, time: i64) -> i64 {
+        time.div_euclid(self.bucket_seconds)
+    }
+
+    /// Total span covered by the window in seconds
+    pub fn span_seconds(&self) -> i64 {
+        self.bucket_seconds.saturating_mul(N as i64)
+    }
+
+    fn slot(bucket: i64) -> usize {
+        bucket.rem_euclid(N as i64) as usize
+    }
+
+    /// Check if a bucket is still held by the window
+    fn holds(&self, bucket: i64) -> bool {
+        bucket <= self.head_bucket && self.head_bucket - bucket < N as i64
+    }
+
+    /// Move the head forward to `bucket`, clearing skipped buckets
+    fn advance_to(&mut self, bucket: i64) {
+        if bucket <= self.head_bucket {
+            return;
+        }
+        let gap = bucket.saturating_sub(self.head_bucket);
+        if gap >= N as i64 {
+            self.counts = [0; N];
+            self.values = [0; N];
+        } else {
+            for b in (self.head_bucket + 1)..=bucket {
+                self.counts[Self::slot(b)] = 0;
+                self.values[Self::slot(b)] = 0;
+            }
+        }
+        self.head_bucket = bucket;
+    }
+
+    /// Record an event
+    pub fn record(&mut self, value: u64) -> Result<()> {
+        self.record_at(Clock::get()?.unix_timestamp, value);
+        Ok(())
+    }
+
+    /// Record an event at `time`. Late events land in their own bucket if
+    /// it is still in the window and are dropped otherwise.
+    /// Returns whether the event was recorded.
+    pub fn record_at(&mut self, time: i64, value: u64) -> bool {
+        let bucket = self.bucket_id(time);
+        self.advance_to(bucket);
+        if !self.holds(bucket) {
+            return false;
+        }
+
+        let slot = Self::slot(bucket);
+        self.counts[slot] = self.counts[slot].saturating_add(1);
+        self.values[slot] = self.values[slot].saturating_add(value as u128);
+        true
+    }
+
+    /// (count, value sum) over buckets whose start lies in `[from, to]`,
+    /// limited to what the window still holds as of `now`
+    pub fn range_at(&self, now: i64, from: i64, to: i64) -> (u64, u128) {
+        let newest = self.bucket_id(now);
+        let (from, to) = (self.bucket_id(from), self.bucket_id(to).min(newest));
+
+        // As of `now`, buckets older than `newest - N + 1` have aged out
+        let oldest = newest.saturating_sub(N as i64 - 1).max(from);
+
+        let mut count = 0u64;
+        let mut value = 0u128;
+        let mut bucket = oldest;
+        while bucket <= to {
+            if self.holds(bucket) {
+                count = count.saturating_add(self.counts[Self::slot(bucket)]);
+                value = value.saturating_add(self.values[Self::slot(bucket)]);
+            }
+            bucket += 1;
+        }
+        (count, value)
+    }
+
+    /// (count, value sum) over the newest `buckets` buckets as of `now`
+    pub fn recent_at(&self, now: i64, buckets: usize) -> (u64, u128) {
+        if buckets == 0 {
+            return (0, 0);
+        }
+        let back = (buckets.min(N) as i64 - 1).saturating_mul(self.bucket_seconds);
+        self.range_at(now, now.saturating_sub(back), now)
+    }
+
+    /// (count, value sum) over the whole window as of `now`
+    pub fn totals_at(&self, now: i64) -> (u64, u128) {
+        self.recent_at(now, N)
+    }
+
+    /// Average value over the newest `buckets` buckets (0 if no events)
+    pub fn average_at(&self, now: i64, buckets: usize) -> u64 {
+        let (count, value) = self.recent_at(now, buckets);
+        if count == 0 {
+            return 0;
+        }
+        (value / count as u128) as u64
+    }
+
+    /// Events per hour over the newest `buckets` buckets
+    pub fn hourly_rate_at(&self, now: i64, buckets: usize) -> u64 {
+        let (count, _) = self.recent_at(now, buckets);
+        let span = (buckets.min(N) as i64).saturating_mul(self.bucket_seconds);
+        if span <= 0 {
+            return 0;
+        }
+        ((count as u128 * 3600) / span as u128) as u64
+    }
+}
+
+/// Snapshot of a `PriceAccumulator`'s cumulative values
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug, InitSpace)]
+pub struct PriceObservation {
+    /// When the snapshot was taken
+    pub timestamp: i64,
+
+    /// Sum of price × seconds the price was held
+    pub cumulative_price_time: u128,
+
+    /// Sum of traded volume
+    pub cumulative_volume: u128,
+
+    /// Sum of price × volume
+    pub cumulative_price_volume: u128,
+}
+
+/// TWAP/VWAP result returned to callers (e.g. via CPI return data)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct PriceQuote {
+    /// Most recent trade price
+    pub last_price: u64,
+
+    /// Time-weighted average price over the window
+    pub twap: u64,
+
+    /// Volume-weighted average price over the window (0 if no volume)
+    pub vwap: u64,
+
+    /// Seconds actually covered by the quote
+    pub window: i64,
+}
+
+/// Cumulative price accumulator for on-chain TWAP/VWAP (Uniswap v2 oracle style)
+///
+/// Each trade adds the previous price weighted by the time it was held,
+/// plus price × volume. Averages over a window are the difference of two
+/// cumulative snapshots divided by elapsed time (TWAP) or volume (VWAP).
+/// A small ring of observations keeps past snapshots on-chain, at least
+/// `MIN_SPACING` seconds apart so bursts of trades can't shrink the
+/// history a quote can reach back to.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct PriceAccumulator {
+    /// Most recent trade price
+    pub last_price: u64,
+
+    /// Timestamp of the most recent trade (0 = no trades yet)
+    pub last_update: i64,
+
+    /// Running cumulative values as of `last_update`
+    pub cumulative: PriceObservation,
+
+    /// Ring buffer of past snapshots
+    pub observations: [PriceObservation; 8],
+
+    /// Next observation slot to write
+    pub observation_index: u8,
+
+    /// Number of observations written (max 8)
+    pub observation_count: u8,
+}
+
+impl PriceAccumulator {
+    /// Number of observations kept
+    pub const OBSERVATIONS: usize = 8;
+
+    /// Minimum seconds between stored observations: a full ring spans at
+    /// least `(OBSERVATIONS - 1) * MIN_SPACING` (70 minutes)
+    pub const MIN_SPACING: i64 = 600;
+
+    /// Record a trade
+    pub fn update(&mut self, price: u64, volume: u64) -> Result<()> {
+        self.update_at(Clock::get()?.unix_timestamp, price, volume);
+        Ok(())
+    }
+
+    /// Record a trade at `now`
+    pub fn update_at(&mut self, now: i64, price: u64, volume: u64) {
+        let mut cumulative = self.cumulative_at(now);
+        cumulative.cumulative_volume = cumulative.cumulative_volume.saturating_add(volume as u128);
+        cumulative.cumulative_price_volume = cumulative
+            .cumulative_price_volume
+            .saturating_add(price as u128 * volume as u128);
+
+        self.cumulative = cumulative;
+        self.last_price = price;
+        self.last_update = now.max(self.last_update);
+
+        // Snapshot the first trade of each spacing interval; later trades
+        // only move the running cumulative
+        let due = match self.newest_observation() {
+            Some(newest) => now >= newest.timestamp.saturating_add(Self::MIN_SPACING),
+            None => true,
+        };
+        if due {
+            self.observations[self.observation_index as usize] = cumulative;
+            self.observation_index = ((self.observation_index as usize + 1) % Self::OBSERVATIONS) as u8;
+            self.observation_count = (self.observation_count + 1).min(Self::OBSERVATIONS as u8);
+        }
+    }
+
+    fn newest_observation(&self) -> Option<PriceObservation> {
+        (self.observation_count > 0).then(|| {
+            self.observations
+                [(self.observation_index as usize + Self::OBSERVATIONS - 1) % Self::OBSERVATIONS]
+        })
+    }
+
+    /// Cumulative values extrapolated to `now` (the last price held since the last trade)
+    pub fn cumulative_at(&self, now: i64) -> PriceObservation {
+        let mut cumulative = self.cumulative;
+        if self.last_update != 0 && now > self.last_update {
+            let elapsed = (now - self.last_update) as u128;
+            cumulative.cumulative_price_time = cumulative
+                .cumulative_price_time
+                .saturating_add(self.last_price as u128 * elapsed);
+        }
+        cumulative.timestamp = now.max(self.last_update);
+        cumulative
+    }
+
+    /// Newest stored observation taken at or before `timestamp`
+    pub fn observation_before(&self, timestamp: i64) -> Option<PriceObservation> {
+        (0..self.observation_count as usize)
+            .map(|age| {
+                let idx = (self.observation_index as usize + Self::OBSERVATIONS - 1 - age)
+                    % Self::OBSERVATIONS;
+                self.observations[idx]
+            })
+            .find(|obs| obs.timestamp <= timestamp)
+    }
+
+    /// Time-weighted average price between two snapshots
+    pub fn twap_between(start: &PriceObservation, end: &PriceObservation) -> Option<u64> {
+        let elapsed = end.timestamp.checked_sub(start.timestamp)?;
+        if elapsed <= 0 {
+            return None;
+        }
+        let delta = end.cumulative_price_time.checked_sub(start.cumulative_price_time)?;
+        Some((delta / elapsed as u128) as u64)
+    }
+
+    /// Volume-weighted average price between two snapshots
+    pub fn vwap_between(start: &PriceObservation, end: &PriceObservation) -> Option<u64> {
+        let volume = end.cumulative_volume.checked_sub(start.cumulative_volume)?;
+        if volume == 0 {
+            return None;
+        }
+        let delta = end.cumulative_price_volume.checked_sub(start.cumulative_price_volume)?;
+        Some((delta / volume) as u64)
+    }
+
+    /// TWAP/VWAP over the last `window` seconds as of `now`, measured from
+    /// the newest observation at or before `now - window`. The quote covers
+    /// at least `window` seconds (up to `MIN_SPACING` more, see
+    /// `PriceQuote::window`); fails if the history doesn't reach back that far.
+    pub fn quote_at(&self, now: i64, window: i64) -> Result<PriceQuote> {
+        let start = self
+            .observation_before(now.saturating_sub(window))
+            .ok_or(StratumError::InsufficientPriceHistory)?;
+        let end = self.cumulative_at(now);
+
+        Ok(PriceQuote {
+            last_price: self.last_price,
+            twap: Self::twap_between(&start, &end).unwrap_or(0),
+            vwap: Self::vwap_between(&start, &end).unwrap_or(0),
+            window: end.timestamp.saturating_sub(start.timestamp),
+        })
+    }
+}
+
+/// Compact record of one event in an `EventRing`
+#[zero_copy]
+#[derive(Debug, PartialEq, Eq)]
+pub struct EventRecord {
+    /// Account that triggered the event (`ArchivableEvent::actor`)
+    pub actor: Pubkey,
+
+    /// Primary value (`ArchivableEvent::value`)
+    pub value: u64,
+
+    /// Slot the event was recorded in
+    pub slot: u64,
+
+    /// Event type tag: the event's Anchor discriminator (zero-padded)
+    pub tag: [u8; 8],
+}
+
+/// Fixed-capacity ring of the most recent events, for zero-copy accounts
+///
+/// Lets dashboards show "last N trades/claims" straight from account data,
+/// without an indexer. Embed it in a program's `#[account(zero_copy)]`
+/// account and pass it to `emit_and_record!` / `record_event!`.
+#[zero_copy]
+pub struct EventRing {
+    /// Total events ever recorded
+    pub total: u64,
+
+    /// Next slot to write
+    pub head: u32,
+
+    /// Number of stored records (max `CAPACITY`)
+    pub len: u32,
+
+    /// Records, oldest overwritten first
+    pub records: [EventRecord; 64],
+}
+
+impl EventRing {
+    /// Number of records kept
+    pub const CAPACITY: usize = 64;
+
+    /// Serialized size
+    pub const SPACE: usize = 8 + 4 + 4 + 56 * 64;
+
+    /// Append a record, overwriting the oldest when full
+    pub fn push(&mut self, record: EventRecord) {
+        self.records[self.head as usize] = record;
+        self.head = ((self.head as usize + 1) % Self::CAPACITY) as u32;
+        self.len = (self.len + 1).min(Self::CAPACITY as u32);
+        self.total = self.total.saturating_add(1);
+    }
+
+    /// Append an event recorded in `slot`
+    pub fn record_at<E: ArchivableEvent + Discriminator>(&mut self, slot: u64, event: &E) {
+        self.push(EventRecord {
+            actor: event.actor(),
+            value: event.value(),
+            slot,
+            tag: event_tag::<E>(),
+        });
+    }
+
+    /// Number of stored records
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Check if no events were recorded
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Record `age` events back (0 = newest)
+    pub fn get(&self, age: usize) -> Option<&EventRecord> {
+        if age >= self.len() {
+            return None;
+        }
+        let idx = (self.head as usize + Self::CAPACITY - 1 - age) % Self::CAPACITY;
+        Some(&self.records[idx])
+    }
+
+    /// Stored records, newest first
+    pub fn recent(&self) -> impl Iterator<Item = &EventRecord> {
+        (0..self.len()).filter_map(move |age| self.get(age))
+    }
+}
+
+/// Anchor discriminator of an event type, zero-padded to 8 bytes
+pub fn event_tag<E: Discriminator>() -> [u8; 8] {
+    let mut tag = [0u8; 8];
+    let len = E::DISCRIMINATOR.len().min(8);
+    tag[..len].copy_from_slice(&E::DISCRIMINATOR[..len]);
+    tag
+}
+
 /// Event anchor trait - for events that should be archived
 pub trait ArchivableEvent: AnchorSerialize {
     /// Get the event type identifier
@@ -167,6 +791,11 @@ pub trait ArchivableEvent: AnchorSerialize {
     /// Get the primary value for summary aggregation
     fn value(&self) -> u64;
 
+    /// Account that triggered the event (default: none)
+    fn actor(&self) -> Pubkey {
+        Pubkey::default()
+    }
+
     /// Compute hash of the event for verification
     fn compute_hash(&self) -> Result<[u8; 32]> {
         let data = self.try_to_vec()?;
@@ -174,6 +803,86 @@ pub trait ArchivableEvent: AnchorSerialize {
     }
 }
 
+/// Destination that `record_event!` feeds events into besides the summary
+pub trait EventSink {
+    /// Record an event whose hash is `event_hash`
+    fn record_event<E: ArchivableEvent + Discriminator>(
+        &mut self,
+        event: &E,
+        event_hash: [u8; 32],
+    ) -> Result<()>;
+}
+
+impl EventSink for EventRing {
+    fn record_event<E: ArchivableEvent + Discriminator>(
+        &mut self,
+        event: &E,
+        _event_hash: [u8; 32],
+    ) -> Result<()> {
+        self.record_at(Clock::get()?.slot, event);
+        Ok(())
+    }
+}
+
+/// Distributions are fed the event value and chained like a summary
+impl EventSink for DistributionSummary {
+    fn record_event<E: ArchivableEvent + Discriminator>(
+        &mut self,
+        event: &E,
+        event_hash: [u8; 32],
+    ) -> Result<()> {
+        self.record_now(event.value())?;
+        self.chain_event(event_hash);
+        Ok(())
+    }
+}
+
+impl<S: EventSink + ?Sized> EventSink for &mut S {
+    fn record_event<E: ArchivableEvent + Discriminator>(
+        &mut self,
+        event: &E,
+        event_hash: [u8; 32],
+    ) -> Result<()> {
+        (**self).record_event(event, event_hash)
+    }
+}
+
+/// Optional sinks (e.g. optional accounts) are skipped when absent
+impl<S: EventSink> EventSink for Option<S> {
+    fn record_event<E: ArchivableEvent + Discriminator>(
+        &mut self,
+        event: &E,
+        event_hash: [u8; 32],
+    ) -> Result<()> {
+        match self {
+            Some(sink) => sink.record_event(event, event_hash),
+            None => Ok(()),
+        }
+    }
+}
+
+/// One link of the event hash chain: H(prev || event_hash || sequence)
+pub fn chain_hash(prev: &[u8; 32], event_hash: &[u8; 32], sequence: u64) -> [u8; 32] {
+    let mut data = Vec::with_capacity(72);
+    data.extend_from_slice(prev);
+    data.extend_from_slice(event_hash);
+    data.extend_from_slice(&sequence.to_le_bytes());
+    simple_hash(&data)
+}
+
+/// Fold event hashes onto a (head, sequence) checkpoint
+pub fn replay_chain(
+    mut head: [u8; 32],
+    mut sequence: u64,
+    event_hashes: &[[u8; 32]],
+) -> ([u8; 32], u64) {
+    for event_hash in event_hashes {
+        sequence = sequence.saturating_add(1);
+        head = chain_hash(&head, event_hash, sequence);
+    }
+    (head, sequence)
+}
+
 /// Simple hash function for when crypto libraries aren't available
 fn simple_hash(data: &[u8]) -> [u8; 32] {
     let mut state = [
@@ -200,15 +909,52 @@ fn simple_hash(data: &[u8]) -> [u8; 32] {
     result
 }
 
-/// Helper macro to emit an event and update history summary
+/// Helper macro to emit an event and update history summary.
+/// Extra arguments are `EventSink`s (e.g. an `EventAccumulator` or
+/// `EventRing`) that also receive the event.
 #[macro_export]
 macro_rules! emit_and_record {
-    ($event:expr, $summary:expr) => {{
+    ($event:expr, $summary:expr $(, $sink:expr)* $(,)?) => {{
+        $crate::record_event!($event, $summary $(, $sink)*);
+        emit!($event);
+    }};
+}
+
+/// Update a history summary (and any extra `EventSink`s) with an event
+/// without emitting it. Pair with `emit_event!` for self-CPI emission.
+#[macro_export]
+macro_rules! record_event {
+    ($event:expr, $summary:expr $(, $sink:expr)* $(,)?) => {{
         let value = $event.value();
         let hash = $event.compute_hash()?;
         $summary.record_now(value)?;
-        $summary.set_last_hash(hash);
-        emit!($event);
+        $summary.chain_event(hash);
+        $($crate::events::EventSink::record_event(&mut $sink, &$event, hash)?;)*
+    }};
+}
+
+/// Emit an event from an instruction handler
+///
+/// When the calling program is built with its `event-cpi` feature, this is
+/// Anchor's `emit_cpi!`: the event is sent as self-CPI instruction data,
+/// which indexers read from inner instructions and RPC log truncation can't
+/// drop. The handler's accounts struct then needs
+/// `#[cfg_attr(feature = "event-cpi", event_cpi)]`. Without the feature it
+/// falls back to `emit!`.
+///
+/// Instructions other programs call via CPI (`pay_cleanup_reward`) use plain
+/// `emit!` instead, so callers' account lists don't depend on the feature.
+#[macro_export]
+macro_rules! emit_event {
+    ($ctx:expr, $event:expr) => {{
+        #[cfg(feature = "event-cpi")]
+        {
+            let event = $event;
+            let ctx = &$ctx;
+            anchor_lang::prelude::emit_cpi!(event);
+        }
+        #[cfg(not(feature = "event-cpi"))]
+        anchor_lang::prelude::emit!($event);
     }};
 }
 
@@ -257,4 +1003,279 @@ mod tests {
         assert_eq!(summary.max_value, 200);
         assert_eq!(summary.average(), 116); // 350 / 3
     }
+
+    #[test]
+    fn test_distribution_summary() {
+        let mut dist = DistributionSummary::default();
+        assert_eq!((dist.variance(), dist.p50()), (0, 0));
+
+        for value in [2, 4, 4, 4, 5, 5, 7, 9] {
+            dist.record(value, 0, 0);
+        }
+        assert_eq!(dist.summary.total_count, 8);
+        assert_eq!(dist.average(), 5);
+        assert_eq!(dist.variance(), 4);
+        assert_eq!(dist.stddev(), 2);
+
+        // Buckets: [2,3] x1, [4,7] x6, [8,15] x1
+        assert_eq!(dist.percentile(1), 3);
+        assert_eq!(dist.p50(), 7);
+        assert_eq!(dist.p90(), 9); // bucket bound 15 clamped to max
+        assert_eq!(dist.percentile(10000), 9);
+    }
+
+    #[test]
+    fn test_distribution_variance_fractional_mean() {
+        // Mean 1e9 + 0.5: true variance 0.25
+        let mut dist = DistributionSummary::default();
+        dist.record(1_000_000_000, 0, 0);
+        dist.record(1_000_000_001, 0, 0);
+        assert_eq!(dist.variance(), 0);
+        assert_eq!(dist.stddev(), 0);
+
+        // Mean 2.5: true variance 1.25
+        let mut dist = DistributionSummary::default();
+        for value in [1, 2, 3, 4] {
+            dist.record(value, 0, 0);
+        }
+        assert_eq!(dist.variance(), 1);
+        assert_eq!(dist.stddev(), 1);
+    }
+
+    #[test]
+    fn test_distribution_buckets() {
+        // (value, bucket)
+        for (value, bucket) in [(0, 0), (1, 1), (2, 2), (3, 2), (4, 3), (u64::MAX, 64)] {
+            assert_eq!(DistributionSummary::bucket_of(value), bucket, "value {}", value);
+            assert!(DistributionSummary::bucket_upper_bound(bucket) >= value);
+        }
+
+        let mut dist = DistributionSummary::default();
+        for _ in 0..99 {
+            dist.record(10, 0, 0);
+        }
+        dist.record(u64::MAX, 0, 0);
+        assert_eq!(dist.p50(), 15);
+        assert_eq!(dist.p99(), 15);
+        assert_eq!(dist.percentile(10000), u64::MAX);
+        assert_eq!(dist.sum_squares, 99 * 100 + (u64::MAX as u128).pow(2));
+
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+    }
+
+    #[derive(AnchorSerialize)]
+    struct TestEvent {
+        id: u64,
+    }
+
+    impl ArchivableEvent for TestEvent {
+        fn event_type(&self) -> &'static str {
+            "test"
+        }
+
+        fn value(&self) -> u64 {
+            self.id
+        }
+    }
+
+    impl Discriminator for TestEvent {
+        const DISCRIMINATOR: &'static [u8] = &[1, 2, 3];
+    }
+
+    #[test]
+    fn test_event_hash_chain() {
+        let events: Vec<TestEvent> = (0..5).map(|id| TestEvent { id }).collect();
+        let mut summary = HistorySummary::default();
+        for event in &events {
+            summary.chain_event(event.compute_hash().unwrap());
+        }
+        assert_eq!(summary.sequence, 5);
+        assert!(summary.verify_chain(&events).unwrap());
+
+        // Dropped, reordered or extra events don't reproduce the head
+        assert!(!summary.verify_chain(&events[..4]).unwrap());
+        let mut reordered: Vec<TestEvent> = (0..5).map(|id| TestEvent { id }).collect();
+        reordered.swap(1, 2);
+        assert!(!summary.verify_chain(&reordered).unwrap());
+
+        // Verifying from a checkpoint
+        let hashes: Vec<[u8; 32]> = events.iter().map(|e| e.compute_hash().unwrap()).collect();
+        let (head, sequence) = replay_chain([0u8; 32], 0, &hashes[..3]);
+        assert!(summary.verify_chain_from(head, sequence, &hashes[3..]));
+        assert!(!summary.verify_chain_from(head, sequence, &hashes[4..]));
+
+        // The same event hash at a different position links differently
+        assert_ne!(
+            chain_hash(&[0u8; 32], &hashes[0], 1),
+            chain_hash(&[0u8; 32], &hashes[0], 2)
+        );
+    }
+
+    #[test]
+    fn test_rolling_window_record_at() {
+        let mut window = RollingWindow::new_at(1000, 100);
+
+        // (value, now, window_count, prev_window_count)
+        let cases = [
+            (10, 1000, 1, 0),
+            (20, 1099, 2, 0),
+            (30, 1100, 1, 2), // boundary rolls
+            (40, 1150, 2, 2),
+            (50, 1250, 1, 2),
+        ];
+
+        for (value, now, count, prev) in cases {
+            window.record_at(now, value);
+            assert_eq!(window.window_count, count, "now {}", now);
+            assert_eq!(window.prev_window_count, prev, "now {}", now);
+        }
+
+        assert_eq!(window.current_average(), 50);
+        assert_eq!(window.change_rate_bps(), -5000);
+    }
+
+    #[test]
+    fn test_sliding_window_buckets() {
+        // 4 buckets of 100s
+        let mut window = SlidingWindow::<4>::new_at(0, 100).unwrap();
+
+        for (value, time) in [(10, 0), (20, 50), (30, 150), (40, 250), (50, 350)] {
+            assert!(window.record_at(time, value));
+        }
+        assert_eq!(window.totals_at(399), (5, 150));
+
+        // Next bucket evicts only the oldest
+        window.record_at(400, 60);
+        assert_eq!(window.totals_at(400), (4, 180));
+        assert_eq!(window.recent_at(400, 2), (2, 110));
+        assert_eq!(window.average_at(400, 2), 55);
+
+        // Sub-span [100, 299] covers buckets 1 and 2
+        assert_eq!(window.range_at(400, 100, 299), (2, 70));
+
+        // Late events go to their bucket if still held, else dropped
+        assert!(window.record_at(150, 5));
+        assert!(!window.record_at(50, 5));
+        assert_eq!(window.range_at(400, 100, 199), (2, 35));
+    }
+
+    #[test]
+    fn test_sliding_window_gaps() {
+        let mut window = HourlyWindow::new_at(0, 3600).unwrap();
+        window.record_at(0, 100);
+        window.record_at(3 * 3600, 100);
+
+        // Skipping two buckets clears just those
+        window.record_at(5 * 3600, 100);
+        assert_eq!(window.totals_at(5 * 3600), (3, 300));
+        assert_eq!(window.recent_at(5 * 3600, 3), (2, 200));
+        assert_eq!(window.hourly_rate_at(5 * 3600, 1), 1);
+        assert_eq!(window.hourly_rate_at(5 * 3600, 0), 0);
+
+        // Reading ahead without recording ages data out too
+        assert_eq!(window.totals_at(24 * 3600), (2, 200));
+        assert_eq!(window.totals_at(100 * 3600), (0, 0));
+
+        // A gap longer than the window clears everything
+        window.record_at(100 * 3600, 7);
+        assert_eq!(window.totals_at(100 * 3600), (1, 7));
+
+        assert!(SlidingWindow::<4>::new_at(0, 0).is_err());
+        assert_eq!(HourlyWindow::SPACE, 16 + 24 * 24);
+        assert_eq!(
+            HourlyWindow::SPACE,
+            window.try_to_vec().unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_price_accumulator() {
+        let mut acc = PriceAccumulator::default();
+        assert!(acc.quote_at(1000, 100).is_err());
+
+        // (price, volume, time)
+        for (price, volume, time) in [(100, 10, 1000), (200, 30, 1100), (150, 10, 1300)] {
+            acc.update_at(time, price, volume);
+        }
+
+        // Price 100 held 100s, 200 held 200s
+        let quote = acc.quote_at(1300, 300).unwrap();
+        assert_eq!(quote.window, 300);
+        assert_eq!(quote.twap, (100 * 100 + 200 * 200) / 300);
+        // VWAP excludes the trade at the start snapshot
+        assert_eq!(quote.vwap, (200 * 30 + 150 * 10) / 40);
+        assert_eq!(quote.last_price, 150);
+
+        // Trades within MIN_SPACING share the first one's observation, so
+        // the quote starts there and extrapolates the last price up to now
+        let later = acc.quote_at(1400, 100).unwrap();
+        assert_eq!(later.window, 400);
+        assert_eq!(later.twap, (100 * 100 + 200 * 200 + 150 * 100) / 400);
+
+        // History shorter than the window is an error, not a shorter quote
+        assert!(acc.quote_at(1300, 10_000).is_err());
+    }
+
+    #[test]
+    fn test_price_observation_ring() {
+        let mut acc = PriceAccumulator::default();
+
+        let spacing = PriceAccumulator::MIN_SPACING;
+
+        // Bursts of trades don't use up observations
+        for t in 0..100 {
+            acc.update_at(1000 + t, 10, 1);
+        }
+        assert_eq!(acc.observation_count, 1);
+        assert_eq!(acc.observations[0].cumulative_volume, 1);
+        assert_eq!(acc.cumulative.cumulative_volume, 100);
+
+        for i in 1..=10 {
+            acc.update_at(1000 + i * spacing, 10, 1);
+        }
+        let now = 1000 + 10 * spacing;
+        assert_eq!(acc.observation_count, PriceAccumulator::OBSERVATIONS as u8);
+        assert_eq!(acc.observation_before(now).unwrap().timestamp, now);
+        assert!(acc.observation_before(1000 + 3 * spacing - 1).is_none());
+
+        // A full ring always reaches back an hour
+        let hour = acc.quote_at(now, 3600).unwrap();
+        assert!(hour.window >= 3600 && hour.window < 3600 + spacing);
+        assert_eq!(hour.twap, 10);
+        assert!(acc.quote_at(now, 8 * spacing).is_err());
+        assert_eq!(PriceAccumulator::INIT_SPACE, 8 + 8 + 56 + 56 * 8 + 1 + 1);
+    }
+
+    #[test]
+    fn test_rolling_window_overflow() {
+        // window_start + window_seconds would overflow
+        let mut window = RollingWindow::new_at(10, i64::MAX);
+        window.record_at(i64::MAX, 1);
+        window.record_at(i64::MAX, 1);
+        assert_eq!(window.window_count, 1);
+        assert_eq!(window.prev_window_count, 1);
+    }
+
+    #[test]
+    fn test_event_ring() {
+        let mut ring: EventRing = bytemuck::Zeroable::zeroed();
+        assert!(ring.is_empty() && ring.get(0).is_none());
+
+        for id in 0..70 {
+            ring.record_at(id + 100, &TestEvent { id });
+        }
+        assert_eq!((ring.len(), ring.total), (EventRing::CAPACITY, 70));
+
+        let newest = ring.get(0).unwrap();
+        assert_eq!((newest.value, newest.slot), (69, 169));
+        assert_eq!(newest.tag, [1, 2, 3, 0, 0, 0, 0, 0]);
+        assert_eq!(ring.get(63).unwrap().value, 6);
+        assert!(ring.get(64).is_none());
+
+        let values: Vec<u64> = ring.recent().take(3).map(|r| r.value).collect();
+        assert_eq!(values, vec![69, 68, 67]);
+        assert_eq!(EventRing::SPACE, std::mem::size_of::<EventRing>());
+    }
 }
@@ -1,6 +1,42 @@
 use anchor_lang::prelude::*;
 use crate::errors::StratumError;
 
+/// Unit that expiry times are measured in
+///
+/// Unix timestamps can be skewed by validators; slots advance monotonically
+/// and suit protocols that reason in blocks (order TTLs, challenge windows).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug, InitSpace)]
+pub enum ExpiryUnit {
+    /// Unix timestamp in seconds
+    #[default]
+    Timestamp,
+    /// Slot number
+    Slot,
+}
+
+impl ExpiryUnit {
+    /// Current time in this unit from a clock
+    pub fn current(&self, clock: &Clock) -> i64 {
+        match self {
+            ExpiryUnit::Timestamp => clock.unix_timestamp,
+            ExpiryUnit::Slot => clock.slot as i64,
+        }
+    }
+
+    /// Current time in this unit from the Clock sysvar
+    pub fn now(&self) -> Result<i64> {
+        Ok(self.current(&Clock::get()?))
+    }
+
+    /// Approximate length of one day in this unit
+    pub fn day(&self) -> i64 {
+        match self {
+            ExpiryUnit::Timestamp => ttl::DAY,
+            ExpiryUnit::Slot => slot_ttl::DAY,
+        }
+    }
+}
+
 /// Standard expiry configuration for accounts that should be cleaned up
 ///
 /// Use cases:
@@ -21,12 +57,41 @@ pub struct ExpiryConfig {
 
     /// Reward for cleanup crank operator (in lamports)
     pub cleanup_reward: u64,
+
+    /// Unit `created_at`, `expires_at` and `grace_period` are measured in
+    pub unit: ExpiryUnit,
 }
 
 impl ExpiryConfig {
     /// Create a new expiry config with TTL in seconds
     pub fn new(ttl_seconds: i64, grace_period: i64, cleanup_reward: u64) -> Result<Self> {
-        let now = Clock::get()?.unix_timestamp;
+        Self::new_at(Clock::get()?.unix_timestamp, ttl_seconds, grace_period, cleanup_reward)
+    }
+
+    /// Create a new expiry config with TTL in slots
+    pub fn new_slots(ttl_slots: i64, grace_slots: i64, cleanup_reward: u64) -> Result<Self> {
+        Self::new_slots_at(ExpiryUnit::Slot.now()?, ttl_slots, grace_slots, cleanup_reward)
+    }
+
+    /// Create a new expiry config with TTL in slots, starting at slot `now`
+    pub fn new_slots_at(
+        now: i64,
+        ttl_slots: i64,
+        grace_slots: i64,
+        cleanup_reward: u64,
+    ) -> Result<Self> {
+        let mut config = Self::new_at(now, ttl_slots, grace_slots, cleanup_reward)?;
+        config.unit = ExpiryUnit::Slot;
+        Ok(config)
+    }
+
+    /// Create a new expiry config with TTL in seconds, starting at `now`
+    pub fn new_at(
+        now: i64,
+        ttl_seconds: i64,
+        grace_period: i64,
+        cleanup_reward: u64,
+    ) -> Result<Self> {
         Ok(Self {
             created_at: now,
             expires_at: if ttl_seconds > 0 {
@@ -36,66 +101,99 @@ impl ExpiryConfig {
             },
             grace_period,
             cleanup_reward,
+            unit: ExpiryUnit::Timestamp,
         })
     }
 
     /// Create expiry config that never expires
     pub fn never() -> Result<Self> {
-        let now = Clock::get()?.unix_timestamp;
-        Ok(Self {
+        Ok(Self::never_at(Clock::get()?.unix_timestamp))
+    }
+
+    /// Create expiry config that never expires, created at `now`
+    pub fn never_at(now: i64) -> Self {
+        Self {
             created_at: now,
             expires_at: 0,
             grace_period: 0,
             cleanup_reward: 0,
-        })
+            unit: ExpiryUnit::Timestamp,
+        }
     }
 
     /// Create expiry config with absolute timestamp
     pub fn at(expires_at: i64, grace_period: i64, cleanup_reward: u64) -> Result<Self> {
-        let now = Clock::get()?.unix_timestamp;
-        Ok(Self {
+        Ok(Self::at_from(
+            Clock::get()?.unix_timestamp,
+            expires_at,
+            grace_period,
+            cleanup_reward,
+        ))
+    }
+
+    /// Create expiry config with absolute timestamp, created at `now`
+    pub fn at_from(now: i64, expires_at: i64, grace_period: i64, cleanup_reward: u64) -> Self {
+        Self {
             created_at: now,
             expires_at,
             grace_period,
             cleanup_reward,
-        })
+            unit: ExpiryUnit::Timestamp,
+        }
     }
 
     /// Check if the record has expired
     pub fn is_expired(&self) -> Result<bool> {
-        if self.expires_at == 0 {
-            return Ok(false);
-        }
-        let now = Clock::get()?.unix_timestamp;
-        Ok(now > self.expires_at)
+        Ok(self.is_expired_at(self.unit.now()?))
+    }
+
+    /// Check if the record has expired at `now` (in the config's unit)
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        self.expires_at != 0 && now > self.expires_at
     }
 
     /// Check if cleanup is allowed (expired + grace period passed)
     pub fn can_cleanup(&self) -> Result<bool> {
+        self.can_cleanup_at(self.unit.now()?)
+    }
+
+    /// Check if cleanup is allowed at `now` (expired + grace period passed)
+    pub fn can_cleanup_at(&self, now: i64) -> Result<bool> {
         if self.expires_at == 0 {
             return Ok(false);
         }
-        let now = Clock::get()?.unix_timestamp;
-        let cleanup_time = self
+        Ok(now > self.cleanup_time()?)
+    }
+
+    /// Time after which cleanup is allowed (expires_at + grace_period)
+    pub fn cleanup_time(&self) -> Result<i64> {
+        Ok(self
             .expires_at
             .checked_add(self.grace_period)
-            .ok_or(StratumError::Overflow)?;
-        Ok(now > cleanup_time)
+            .ok_or(StratumError::Overflow)?)
     }
 
     /// Get remaining time until expiry (negative if expired)
     pub fn time_remaining(&self) -> Result<i64> {
+        Ok(self.time_remaining_at(self.unit.now()?))
+    }
+
+    /// Get remaining time until expiry at `now` (negative if expired)
+    pub fn time_remaining_at(&self, now: i64) -> i64 {
         if self.expires_at == 0 {
-            return Ok(i64::MAX); // Never expires
+            return i64::MAX; // Never expires
         }
-        let now = Clock::get()?.unix_timestamp;
-        Ok(self.expires_at - now)
+        self.expires_at.saturating_sub(now)
     }
 
     /// Get time since creation
     pub fn age(&self) -> Result<i64> {
-        let now = Clock::get()?.unix_timestamp;
-        Ok(now - self.created_at)
+        Ok(self.age_at(self.unit.now()?))
+    }
+
+    /// Get time since creation at `now`
+    pub fn age_at(&self, now: i64) -> i64 {
+        now.saturating_sub(self.created_at)
     }
 
     /// Extend the expiry time
@@ -108,9 +206,13 @@ impl ExpiryConfig {
         Ok(())
     }
 
-    /// Set expiry to a specific timestamp
+    /// Set expiry to a specific time (in the config's unit)
     pub fn set_expiry(&mut self, new_expires_at: i64) -> Result<()> {
-        let now = Clock::get()?.unix_timestamp;
+        self.set_expiry_at(self.unit.now()?, new_expires_at)
+    }
+
+    /// Set expiry to a specific timestamp, which must be after `now`
+    pub fn set_expiry_at(&mut self, now: i64, new_expires_at: i64) -> Result<()> {
         require!(new_expires_at > now, StratumError::InvalidConfig);
         self.expires_at = new_expires_at;
         Ok(())
@@ -130,14 +232,25 @@ pub trait Expirable {
         self.expiry().is_expired()
     }
 
+    /// Check if expired at `now`
+    fn is_expired_at(&self, now: i64) -> bool {
+        self.expiry().is_expired_at(now)
+    }
+
     /// Check if cleanup is allowed
     fn can_cleanup(&self) -> Result<bool> {
         self.expiry().can_cleanup()
     }
+
+    /// Check if cleanup is allowed at `now`
+    fn can_cleanup_at(&self, now: i64) -> Result<bool> {
+        self.expiry().can_cleanup_at(now)
+    }
 }
 
-/// Cleanup receipt - record of a successful cleanup
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+/// Cleanup receipt - record of a successful cleanup, emitted as an event
+#[event]
+#[derive(Clone)]
 pub struct CleanupReceipt {
     /// Account that was cleaned up
     pub cleaned_account: Pubkey,
@@ -157,13 +270,23 @@ pub struct CleanupReceipt {
 
 /// Helper to validate cleanup is allowed
 pub fn require_cleanup_allowed(expiry: &ExpiryConfig) -> Result<()> {
-    require!(expiry.can_cleanup()?, StratumError::NotExpired);
+    require_cleanup_allowed_at(expiry, expiry.unit.now()?)
+}
+
+/// Helper to validate cleanup is allowed at `now`
+pub fn require_cleanup_allowed_at(expiry: &ExpiryConfig, now: i64) -> Result<()> {
+    require!(expiry.can_cleanup_at(now)?, StratumError::NotExpired);
     Ok(())
 }
 
 /// Helper to validate record is not expired
 pub fn require_not_expired(expiry: &ExpiryConfig) -> Result<()> {
-    require!(!expiry.is_expired()?, StratumError::AlreadyExpired);
+    require_not_expired_at(expiry, expiry.unit.now()?)
+}
+
+/// Helper to validate record is not expired at `now`
+pub fn require_not_expired_at(expiry: &ExpiryConfig, now: i64) -> Result<()> {
+    require!(!expiry.is_expired_at(now), StratumError::AlreadyExpired);
     Ok(())
 }
 
@@ -188,11 +311,27 @@ pub mod ttl {
     pub const YEAR: i64 = 31536000;
 }
 
+/// Approximate slot counts for common TTL values (400ms target slot time)
+pub mod slot_ttl {
+    /// ~1 hour in slots
+    pub const HOUR: i64 = 9_000;
+    /// ~1 day in slots
+    pub const DAY: i64 = 216_000;
+    /// ~1 week in slots
+    pub const WEEK: i64 = 1_512_000;
+    /// ~1 epoch in slots
+    pub const EPOCH: i64 = 432_000;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    fn config(expires_at: i64, grace_period: i64) -> ExpiryConfig {
+        ExpiryConfig::at_from(0, expires_at, grace_period, 5000)
+    }
 
-    // Note: Clock::get() won't work in unit tests, so we test the logic separately
     #[test]
     fn test_ttl_constants() {
         assert_eq!(ttl::HOUR, 3600);
@@ -209,4 +348,153 @@ mod tests {
         assert_eq!(calculate_cleanup_reward(100_000), 5000);
         assert_eq!(calculate_cleanup_reward(0), 5000);
     }
+
+    #[test]
+    fn test_new_at() {
+        let config = ExpiryConfig::new_at(1000, ttl::HOUR, 60, 5000).unwrap();
+        assert_eq!(config.created_at, 1000);
+        assert_eq!(config.expires_at, 1000 + ttl::HOUR);
+
+        // Zero or negative TTL never expires
+        assert_eq!(ExpiryConfig::new_at(1000, 0, 60, 5000).unwrap().expires_at, 0);
+        assert_eq!(ExpiryConfig::new_at(1000, -5, 60, 5000).unwrap().expires_at, 0);
+
+        // TTL overflow
+        assert!(ExpiryConfig::new_at(i64::MAX, 1, 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_expiry_table() {
+        // (expires_at, grace_period, now, is_expired, can_cleanup)
+        let cases = [
+            // Never expires
+            (0, 0, 0, false, false),
+            (0, 100, i64::MAX, false, false),
+            // Exactly at expiry is not expired
+            (1000, 100, 1000, false, false),
+            (1000, 100, 1001, true, false),
+            // Exactly at end of grace is not cleanable
+            (1000, 100, 1100, true, false),
+            (1000, 100, 1101, true, true),
+            // Zero grace: cleanable as soon as expired
+            (1000, 0, 1000, false, false),
+            (1000, 0, 1001, true, true),
+            // Before expiry
+            (1000, 100, 0, false, false),
+            (1000, 100, i64::MIN, false, false),
+        ];
+
+        for (expires_at, grace, now, expired, cleanable) in cases {
+            let config = config(expires_at, grace);
+            assert_eq!(config.is_expired_at(now), expired, "expired {:?}", (expires_at, grace, now));
+            assert_eq!(
+                config.can_cleanup_at(now).unwrap(),
+                cleanable,
+                "cleanable {:?}",
+                (expires_at, grace, now)
+            );
+            assert_eq!(require_not_expired_at(&config, now).is_ok(), !expired);
+            assert_eq!(require_cleanup_allowed_at(&config, now).is_ok(), cleanable);
+        }
+    }
+
+    #[test]
+    fn test_cleanup_time_overflow() {
+        let config = config(i64::MAX - 10, 100);
+        assert!(config.cleanup_time().is_err());
+        assert!(config.can_cleanup_at(0).is_err());
+        // Expiry itself is still answerable
+        assert!(!config.is_expired_at(0));
+    }
+
+    #[test]
+    fn test_time_remaining_and_age() {
+        let config = ExpiryConfig::at_from(100, 1000, 0, 0);
+        assert_eq!(config.time_remaining_at(400), 600);
+        assert_eq!(config.time_remaining_at(1500), -500);
+        assert_eq!(config.time_remaining_at(i64::MIN), i64::MAX);
+        assert_eq!(config.age_at(400), 300);
+        assert_eq!(config.age_at(i64::MIN), i64::MIN);
+
+        assert_eq!(ExpiryConfig::never_at(100).time_remaining_at(400), i64::MAX);
+    }
+
+    #[test]
+    fn test_extend_and_set_expiry() {
+        let mut config = config(1000, 0);
+        config.extend(500).unwrap();
+        assert_eq!(config.expires_at, 1500);
+        assert!(config.extend(i64::MAX).is_err());
+
+        assert!(config.set_expiry_at(2000, 2000).is_err());
+        config.set_expiry_at(2000, 2001).unwrap();
+        assert_eq!(config.expires_at, 2001);
+
+        // Never-expiring configs can't be extended
+        assert!(ExpiryConfig::never_at(0).extend(10).is_err());
+    }
+
+    #[test]
+    fn test_slot_unit() {
+        let config = ExpiryConfig::new_slots_at(1_000, slot_ttl::HOUR, 150, 5000).unwrap();
+        assert_eq!(config.unit, ExpiryUnit::Slot);
+        assert_eq!(config.expires_at, 1_000 + slot_ttl::HOUR);
+        assert!(config.is_expired_at(1_001 + slot_ttl::HOUR));
+        assert!(!config.can_cleanup_at(1_150 + slot_ttl::HOUR).unwrap());
+        assert!(config.can_cleanup_at(1_151 + slot_ttl::HOUR).unwrap());
+
+        let clock = Clock {
+            slot: 42,
+            unix_timestamp: 1_700_000_000,
+            ..Clock::default()
+        };
+        assert_eq!(ExpiryUnit::Slot.current(&clock), 42);
+        assert_eq!(ExpiryUnit::Timestamp.current(&clock), 1_700_000_000);
+        assert_eq!(ExpiryUnit::Slot.day(), slot_ttl::DAY);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_cleanup_implies_expired(
+            expires_at in any::<i64>(),
+            grace in 0i64..=ttl::YEAR,
+            now in any::<i64>(),
+        ) {
+            let config = config(expires_at, grace);
+            if let Ok(true) = config.can_cleanup_at(now) {
+                prop_assert!(config.is_expired_at(now));
+            }
+        }
+
+        #[test]
+        fn prop_expiry_is_monotonic(
+            expires_at in any::<i64>(),
+            grace in any::<i64>(),
+            now in any::<i64>(),
+            later in any::<i64>(),
+        ) {
+            let config = config(expires_at, grace);
+            let (earlier, later) = (now.min(later), now.max(later));
+            if config.is_expired_at(earlier) {
+                prop_assert!(config.is_expired_at(later));
+            }
+            if let (Ok(true), Ok(cleanable)) =
+                (config.can_cleanup_at(earlier), config.can_cleanup_at(later))
+            {
+                prop_assert!(cleanable);
+            }
+        }
+
+        #[test]
+        fn prop_new_at_never_panics(now in any::<i64>(), ttl in any::<i64>(), grace in any::<i64>()) {
+            match ExpiryConfig::new_at(now, ttl, grace, 0) {
+                Ok(config) => {
+                    prop_assert_eq!(config.created_at, now);
+                    prop_assert!(config.expires_at == 0 || config.expires_at > now);
+                    prop_assert_eq!(config.time_remaining_at(now), if ttl > 0 { ttl } else { i64::MAX });
+                }
+                Err(_) => prop_assert!(ttl > 0 && now.checked_add(ttl).is_none()),
+            }
+        }
+    }
 }
@@ -0,0 +1,292 @@
+use anchor_lang::prelude::*;
+use crate::errors::StratumError;
+use crate::expiry::ExpiryUnit;
+
+/// An item registered in an expiry index
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct ExpiryEntry {
+    /// Time after which the item can be cleaned up (expires_at + grace_period)
+    pub due_at: i64,
+
+    /// The expiring account
+    pub item: Pubkey,
+}
+
+/// Bounded min-heap of expiring items, ordered by `due_at`
+///
+/// Lets crankers find cleanup work without scanning every account or
+/// replaying events.
+///
+/// Usage:
+/// - Programs register an item's cleanup time when they create it
+///   (typically `ExpiryConfig::cleanup_time()`) and remove it on cleanup
+/// - Crankers read the due items (earliest first) and call the owning
+///   program's cleanup instruction for each
+///
+/// The index authority is usually a program PDA that signs via CPI.
+#[account]
+#[derive(InitSpace)]
+pub struct ExpiryIndex {
+    /// Authority that can register and remove items
+    pub authority: Pubkey,
+
+    /// Unit `due_at` values are measured in
+    pub unit: ExpiryUnit,
+
+    /// Maximum number of entries
+    pub capacity: u16,
+
+    /// Total items ever registered
+    pub total_registered: u64,
+
+    /// Total items removed
+    pub total_removed: u64,
+
+    /// Heap-ordered entries (entries[0] is due first)
+    #[max_len(200)]
+    pub entries: Vec<ExpiryEntry>,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ExpiryIndex {
+    /// Largest supported capacity (keeps the account under the CPI init limit)
+    pub const MAX_CAPACITY: u16 = 200;
+
+    /// Most items a due query can return (return data is capped at 1024 bytes)
+    pub const MAX_DUE_QUERY: u8 = 31;
+
+    /// Account space (including discriminator) for a capacity
+    pub fn space(capacity: u16) -> usize {
+        8 + // discriminator
+            32 + // authority
+            ExpiryUnit::INIT_SPACE + // unit
+            2 +  // capacity
+            8 +  // total_registered
+            8 +  // total_removed
+            (4 + ExpiryEntry::INIT_SPACE * capacity as usize) + // entries
+            1 // bump
+    }
+
+    /// Initialize an empty index
+    pub fn initialize(
+        &mut self,
+        authority: Pubkey,
+        unit: ExpiryUnit,
+        capacity: u16,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            capacity > 0 && capacity <= Self::MAX_CAPACITY,
+            StratumError::InvalidConfig
+        );
+
+        self.authority = authority;
+        self.unit = unit;
+        self.capacity = capacity;
+        self.total_registered = 0;
+        self.total_removed = 0;
+        self.entries = Vec::with_capacity(capacity as usize);
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    /// Number of indexed items
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no items are indexed
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Check if the index is at capacity
+    pub fn is_full(&self) -> bool {
+        self.entries.len() >= self.capacity as usize
+    }
+
+    /// The item due first
+    pub fn peek(&self) -> Option<&ExpiryEntry> {
+        self.entries.first()
+    }
+
+    /// Check if an item is indexed
+    pub fn contains(&self, item: &Pubkey) -> bool {
+        self.position(item).is_some()
+    }
+
+    /// Register an item's cleanup time
+    pub fn insert(&mut self, item: Pubkey, due_at: i64) -> Result<()> {
+        require!(!self.is_full(), StratumError::ExpiryIndexFull);
+        require!(!self.contains(&item), StratumError::ItemAlreadyIndexed);
+
+        self.entries.push(ExpiryEntry { due_at, item });
+        self.sift_up(self.entries.len() - 1);
+        self.total_registered = self.total_registered.saturating_add(1);
+
+        Ok(())
+    }
+
+    /// Remove an item (e.g. after it was cleaned up or extended)
+    pub fn remove(&mut self, item: &Pubkey) -> Result<ExpiryEntry> {
+        let pos = self.position(item).ok_or(StratumError::ItemNotIndexed)?;
+        Ok(self.remove_at(pos))
+    }
+
+    /// Pop the first item if it is due at `now`
+    pub fn pop_due(&mut self, now: i64) -> Option<ExpiryEntry> {
+        match self.peek() {
+            Some(entry) if now > entry.due_at => Some(self.remove_at(0)),
+            _ => None,
+        }
+    }
+
+    /// Items due at `now`, earliest first, at most `limit`
+    pub fn due(&self, now: i64, limit: usize) -> Vec<ExpiryEntry> {
+        // Walk the heap, only descending below entries that are due
+        let mut due = Vec::new();
+        let mut stack = vec![0usize];
+        while let Some(i) = stack.pop() {
+            match self.entries.get(i) {
+                Some(entry) if now > entry.due_at => {
+                    due.push(*entry);
+                    stack.push(2 * i + 1);
+                    stack.push(2 * i + 2);
+                }
+                _ => {}
+            }
+        }
+
+        due.sort_by_key(|e| e.due_at);
+        due.truncate(limit);
+        due
+    }
+
+    fn position(&self, item: &Pubkey) -> Option<usize> {
+        self.entries.iter().position(|e| e.item == *item)
+    }
+
+    fn remove_at(&mut self, pos: usize) -> ExpiryEntry {
+        let removed = self.entries.swap_remove(pos);
+        if pos < self.entries.len() {
+            self.sift_down(pos);
+            self.sift_up(pos);
+        }
+        self.total_removed = self.total_removed.saturating_add(1);
+        removed
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if self.entries[i].due_at >= self.entries[parent].due_at {
+                break;
+            }
+            self.entries.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        let len = self.entries.len();
+        loop {
+            let mut smallest = i;
+            for child in [2 * i + 1, 2 * i + 2] {
+                if child < len && self.entries[child].due_at < self.entries[smallest].due_at {
+                    smallest = child;
+                }
+            }
+            if smallest == i {
+                break;
+            }
+            self.entries.swap(i, smallest);
+            i = smallest;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_index(capacity: u16) -> ExpiryIndex {
+        let mut index = ExpiryIndex {
+            authority: Pubkey::default(),
+            unit: ExpiryUnit::Timestamp,
+            capacity: 0,
+            total_registered: 0,
+            total_removed: 0,
+            entries: vec![],
+            bump: 0,
+        };
+        index
+            .initialize(Pubkey::default(), ExpiryUnit::Timestamp, capacity, 0)
+            .unwrap();
+        index
+    }
+
+    #[test]
+    fn test_heap_order() {
+        let mut index = new_index(16);
+        let items: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        let due_at = [50, 10, 80, 30, 70, 20, 60, 40];
+        for (item, due) in items.iter().zip(due_at) {
+            index.insert(*item, due).unwrap();
+        }
+
+        // Removing from the middle keeps the heap valid
+        index.remove(&items[3]).unwrap();
+        assert!(index.remove(&items[3]).is_err());
+
+        let mut popped = vec![];
+        while let Some(entry) = index.pop_due(i64::MAX) {
+            popped.push(entry.due_at);
+        }
+        assert_eq!(popped, vec![10, 20, 40, 50, 60, 70, 80]);
+        assert_eq!(index.total_registered, 8);
+        assert_eq!(index.total_removed, 8);
+    }
+
+    #[test]
+    fn test_due_query() {
+        let mut index = new_index(16);
+        for due in [100, 5, 40, 15, 90, 25] {
+            index.insert(Pubkey::new_unique(), due).unwrap();
+        }
+
+        let due: Vec<i64> = index.due(30, 10).iter().map(|e| e.due_at).collect();
+        assert_eq!(due, vec![5, 15, 25]);
+        assert_eq!(index.due(30, 2).len(), 2);
+
+        // Due means strictly past the cleanup time
+        assert!(index.due(5, 10).is_empty());
+        assert!(index.pop_due(5).is_none());
+        assert_eq!(index.pop_due(6).unwrap().due_at, 5);
+    }
+
+    #[test]
+    fn test_capacity_and_duplicates() {
+        let mut index = new_index(2);
+        let item = Pubkey::new_unique();
+        index.insert(item, 1).unwrap();
+        assert!(index.insert(item, 2).is_err());
+        index.insert(Pubkey::new_unique(), 2).unwrap();
+        assert!(index.is_full());
+        assert!(index.insert(Pubkey::new_unique(), 3).is_err());
+
+        let mut other = new_index(1);
+        assert!(other
+            .initialize(Pubkey::default(), ExpiryUnit::Slot, 0, 0)
+            .is_err());
+        assert!(other
+            .initialize(Pubkey::default(), ExpiryUnit::Slot, ExpiryIndex::MAX_CAPACITY + 1, 0)
+            .is_err());
+        assert_eq!(
+            ExpiryIndex::space(ExpiryIndex::MAX_CAPACITY),
+            8 + ExpiryIndex::INIT_SPACE
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke;
+use crate::errors::StratumError;
+use crate::expiry::ExpiryConfig;
+
+/// SPL Token Program ID
+pub mod spl_token {
+    use anchor_lang::declare_id;
+    declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+}
+
+/// Terms for paid renewal of an expiring account
+///
+/// Turns an `ExpiryConfig` into a lease: renewers pay `price_per_unit` for
+/// each second (or slot, for slot-based expiry) of extension, and the
+/// payment goes to `beneficiary`.
+///
+/// Use cases:
+/// - Name registrations
+/// - Rented data slots
+/// - Subscriptions kept alive by third parties
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug, InitSpace)]
+pub struct LeaseTerms {
+    /// Receives renewal payments (wallet for lamports, token account owner for tokens)
+    pub beneficiary: Pubkey,
+
+    /// Mint renewals are paid in (Pubkey::default() = lamports)
+    pub payment_mint: Pubkey,
+
+    /// Price per unit of extension, in lamports or token base units
+    pub price_per_unit: u64,
+
+    /// Maximum time the lease may run ahead of now (0 = unlimited)
+    pub max_term: i64,
+
+    /// Whether anyone can renew, or only the owner
+    pub open_renewal: bool,
+}
+
+impl LeaseTerms {
+    /// Check if renewals are paid in lamports
+    pub fn pays_in_lamports(&self) -> bool {
+        self.payment_mint == Pubkey::default()
+    }
+
+    /// Price of an extension
+    pub fn price(&self, extension: i64) -> Result<u64> {
+        require!(extension > 0, StratumError::InvalidConfig);
+        Ok(self
+            .price_per_unit
+            .checked_mul(extension as u64)
+            .ok_or(StratumError::Overflow)?)
+    }
+
+    /// Renew `expiry` by `extension` at `now`, returning the price to pay.
+    ///
+    /// Extensions stack on the current expiry, or start from `now` if the
+    /// lease lapsed but is still within its grace period. Leases that can
+    /// already be cleaned up can't be renewed.
+    pub fn renew_at(
+        &self,
+        expiry: &mut ExpiryConfig,
+        now: i64,
+        extension: i64,
+        by_owner: bool,
+    ) -> Result<u64> {
+        require!(self.open_renewal || by_owner, StratumError::Unauthorized);
+        require!(expiry.expires_at > 0, StratumError::InvalidConfig);
+        require!(!expiry.can_cleanup_at(now)?, StratumError::AlreadyExpired);
+
+        let price = self.price(extension)?;
+        let new_expires_at = expiry
+            .expires_at
+            .max(now)
+            .checked_add(extension)
+            .ok_or(StratumError::Overflow)?;
+
+        if self.max_term > 0 {
+            require!(
+                new_expires_at.saturating_sub(now) <= self.max_term,
+                StratumError::LeaseTermExceeded
+            );
+        }
+
+        expiry.expires_at = new_expires_at;
+        Ok(price)
+    }
+}
+
+/// Transfer SPL tokens signed by `authority` (a transaction signer)
+pub fn transfer_tokens<'info>(
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let ix = anchor_lang::solana_program::instruction::Instruction {
+        program_id: spl_token::ID,
+        accounts: vec![
+            anchor_lang::solana_program::instruction::AccountMeta::new(*from.key, false),
+            anchor_lang::solana_program::instruction::AccountMeta::new(*to.key, false),
+            anchor_lang::solana_program::instruction::AccountMeta::new_readonly(*authority.key, true),
+        ],
+        data: {
+            let mut data = vec![3u8]; // Transfer instruction discriminator
+            data.extend_from_slice(&amount.to_le_bytes());
+            data
+        },
+    };
+    invoke(&ix, &[from, to, authority, token_program])?;
+    Ok(())
+}
+
+/// Read (mint, owner) from an SPL token account
+pub fn token_account_mint_and_owner(account: &AccountInfo) -> Result<(Pubkey, Pubkey)> {
+    require!(
+        account.owner == &spl_token::ID,
+        StratumError::InvalidTokenAccount
+    );
+    let data = account.try_borrow_data()?;
+    require!(data.len() >= 64, StratumError::InvalidTokenAccount);
+
+    let mint = Pubkey::try_from(&data[0..32]).map_err(|_| StratumError::InvalidTokenAccount)?;
+    let owner = Pubkey::try_from(&data[32..64]).map_err(|_| StratumError::InvalidTokenAccount)?;
+    Ok((mint, owner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(price_per_unit: u64, max_term: i64, open_renewal: bool) -> LeaseTerms {
+        LeaseTerms {
+            beneficiary: Pubkey::new_unique(),
+            payment_mint: Pubkey::default(),
+            price_per_unit,
+            max_term,
+            open_renewal,
+        }
+    }
+
+    #[test]
+    fn test_renewal_pricing() {
+        let terms = terms(10, 0, true);
+        let mut expiry = ExpiryConfig::at_from(0, 1000, 100, 0);
+
+        // Active lease: extension stacks on the current expiry
+        assert_eq!(terms.renew_at(&mut expiry, 500, 200, false).unwrap(), 2000);
+        assert_eq!(expiry.expires_at, 1200);
+
+        // Lapsed but in grace: extension starts from now
+        assert_eq!(terms.renew_at(&mut expiry, 1250, 50, false).unwrap(), 500);
+        assert_eq!(expiry.expires_at, 1300);
+
+        // Past grace: no renewal
+        assert!(terms.renew_at(&mut expiry, 1401, 50, false).is_err());
+        assert_eq!(expiry.expires_at, 1300);
+
+        assert!(terms.price(0).is_err());
+        assert!(LeaseTerms { price_per_unit: u64::MAX, ..terms }.price(2).is_err());
+    }
+
+    #[test]
+    fn test_renewal_rules() {
+        let mut expiry = ExpiryConfig::at_from(0, 1000, 100, 0);
+
+        // Owner-only leases
+        let owner_only = terms(1, 0, false);
+        assert!(owner_only.renew_at(&mut expiry, 0, 10, false).is_err());
+        assert!(owner_only.renew_at(&mut expiry, 0, 10, true).is_ok());
+
+        // Max term caps how far ahead a lease can run
+        let capped = terms(1, 1500, true);
+        assert!(capped.renew_at(&mut expiry, 0, 500, false).is_err());
+        assert!(capped.renew_at(&mut expiry, 0, 490, false).is_ok());
+        assert_eq!(expiry.expires_at, 1500);
+
+        // Never-expiring configs aren't leases
+        let mut never = ExpiryConfig::never_at(0);
+        assert!(capped.renew_at(&mut never, 0, 10, true).is_err());
+
+        assert!(terms(1, 0, true).pays_in_lamports());
+    }
+}
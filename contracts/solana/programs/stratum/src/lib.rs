@@ -1,20 +1,38 @@
 use anchor_lang::prelude::*;
 
 pub mod bitfield;
+pub mod bitset;
+pub mod checkpoint;
 pub mod dynamic_expiry;
 pub mod errors;
 pub mod events;
 pub mod expiry;
+pub mod expiry_index;
+pub mod lease;
 pub mod merkle;
+pub mod migrate;
+pub mod restore;
 pub mod resurrection;
+pub mod reward_pool;
+pub mod state_field;
+pub mod ttl_record;
 
 pub use bitfield::*;
+pub use bitset::*;
+pub use checkpoint::*;
 pub use dynamic_expiry::*;
 pub use errors::*;
 pub use events::*;
 pub use expiry::*;
+pub use expiry_index::*;
+pub use lease::*;
 pub use merkle::*;
+pub use migrate::*;
+pub use restore::*;
 pub use resurrection::*;
+pub use reward_pool::*;
+pub use state_field::*;
+pub use ttl_record::*;
 
 declare_id!("97VX5yBvf55TdgpV6Cmc7pXdgshWyeCrHvn71bX6CHcJ");
 
@@ -22,10 +40,17 @@ declare_id!("97VX5yBvf55TdgpV6Cmc7pXdgshWyeCrHvn71bX6CHcJ");
 ///
 /// Components:
 /// - Bitfield: Compact tracking for claims, spent flags, etc.
+/// - State field: Packed multi-bit state per slot with compare-and-set
 /// - Merkle: Merkle tree commitments and proof verification
 /// - Expiry: TTL and cleanup crank patterns
+/// - Expiry index: Min-heap of cleanup times so crankers can find due items
+/// - TTL records: Generic expiring payloads with permissionless cleanup
+/// - Leases: Paid renewal of expiring accounts
+/// - Reward pools: Funded lamport pools for cleanup crank rewards
 /// - Events: History summarization without state bloat
+/// - Event checkpoints: Merkle accumulators proving past events
 /// - Resurrection: Archive state and restore with proofs
+/// - Migration: Legacy layouts for upgrading accounts in place
 #[program]
 pub mod stratum {
     use super::*;
@@ -58,6 +83,7 @@ pub mod stratum {
             ctx.accounts.authority.key(),
             ctx.accounts.registry.key(),
             chunk_index,
+            ctx.accounts.registry.generation,
             ctx.bumps.chunk,
         );
 
@@ -70,6 +96,7 @@ pub mod stratum {
     /// Set a bit in a bitfield chunk
     pub fn set_bit(ctx: Context<ModifyBitfield>, index: u16) -> Result<()> {
         let chunk = &mut ctx.accounts.chunk;
+        chunk.sync_generation(ctx.accounts.registry.generation);
         let newly_set = chunk.set(index)?;
 
         if newly_set {
@@ -83,16 +110,158 @@ pub mod stratum {
     /// Unset a bit in a bitfield chunk
     pub fn unset_bit(ctx: Context<ModifyBitfield>, index: u16) -> Result<()> {
         let chunk = &mut ctx.accounts.chunk;
+        chunk.sync_generation(ctx.accounts.registry.generation);
         let was_set = chunk.unset(index)?;
 
         if was_set {
             let registry = &mut ctx.accounts.registry;
             registry.record_unset();
+            registry.release(chunk.global_index(index));
         }
 
         Ok(())
     }
 
+    /// Claim the next free slot in a bitfield chunk.
+    /// The allocated global index is returned to the caller via return data.
+    pub fn allocate_slot(ctx: Context<ModifyBitfield>) -> Result<u32> {
+        let registry = &mut ctx.accounts.registry;
+        let chunk = &mut ctx.accounts.chunk;
+        registry.allocate(chunk)
+    }
+
+    /// Freeze a bitfield chunk so its bits can be snapshotted for archiving
+    pub fn freeze_bitfield_chunk(ctx: Context<ModifyBitfield>) -> Result<()> {
+        ctx.accounts.chunk.freeze()
+    }
+
+    /// Archive a frozen bitfield chunk: hash its bits into an archive leaf,
+    /// append it to the archive registry and close the chunk, leaving an
+    /// `ArchivedChunk` tombstone so the chunk can't be re-created.
+    pub fn archive_bitfield_chunk(ctx: Context<ArchiveBitfieldChunk>) -> Result<()> {
+        let chunk = &ctx.accounts.chunk;
+        require!(chunk.is_frozen, StratumError::ChunkNotFrozen);
+        let generation = ctx.accounts.registry.generation;
+
+        let archive_registry = &mut ctx.accounts.archive_registry;
+        let archive_index = archive_registry.append_pending()?;
+
+        let record = ArchivedRecord::new(
+            chunk.key(),
+            chunk.authority,
+            crate::ID,
+            archive_index,
+            &chunk.archive_data(),
+        )?;
+
+        let tombstone = &mut ctx.accounts.tombstone;
+        tombstone.registry = chunk.registry;
+        tombstone.chunk_index = chunk.chunk_index;
+        tombstone.archive_registry = archive_registry.key();
+        tombstone.archive_index = archive_index;
+        tombstone.bump = ctx.bumps.tombstone;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.chunks_created = registry.chunks_created.saturating_sub(1);
+        // Bits of a stale chunk were already dropped from `total_set` by the roll
+        if chunk.is_current(generation) {
+            registry.total_set = registry.total_set.saturating_sub(chunk.set_count as u64);
+        }
+
+        let registry = ctx.accounts.archive_registry.key();
+        emit_event!(ctx, RecordArchived {
+            registry,
+            account: record.account,
+            owner: record.owner,
+            archive_index,
+            data_hash: record.data_hash,
+        });
+        emit_event!(ctx, RecordOwnerProgram {
+            registry,
+            archive_index,
+            owner_program: record.owner_program,
+        });
+
+        Ok(())
+    }
+
+    /// Start a new bitfield generation, logically clearing every chunk.
+    /// Chunks are reset lazily the next time they are modified.
+    pub fn roll_bitfield_generation(ctx: Context<RollBitfieldGeneration>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        let previous_total_set = registry.roll_generation()?;
+
+        emit_event!(ctx, BitfieldGenerationRolled {
+            registry: registry.key(),
+            generation: registry.generation,
+            previous_total_set,
+        });
+
+        Ok(())
+    }
+
+    // =========================================================================
+    // State Field Instructions
+    // =========================================================================
+
+    /// Create a new state field registry
+    pub fn create_state_field_registry(
+        ctx: Context<CreateStateFieldRegistry>,
+        bits_per_entry: u8,
+        total_capacity: u64,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.initialize(
+            ctx.accounts.authority.key(),
+            bits_per_entry,
+            total_capacity,
+            ctx.bumps.registry,
+        )
+    }
+
+    /// Create a new state field chunk
+    pub fn create_state_field_chunk(
+        ctx: Context<CreateStateFieldChunk>,
+        chunk_index: u32,
+    ) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        require!(
+            chunk_index
+                < StateFieldRegistry::chunks_needed(registry.total_capacity, registry.bits_per_entry),
+            StratumError::IndexOutOfBounds
+        );
+
+        let chunk = &mut ctx.accounts.chunk;
+        chunk.initialize(
+            ctx.accounts.authority.key(),
+            ctx.accounts.registry.key(),
+            chunk_index,
+            ctx.accounts.registry.bits_per_entry,
+            ctx.bumps.chunk,
+        )?;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.chunks_created = registry.chunks_created.saturating_add(1);
+
+        Ok(())
+    }
+
+    /// Transition an entry from `expected` to `new_state` (compare-and-set)
+    pub fn transition_state(
+        ctx: Context<ModifyStateField>,
+        index: u16,
+        expected: u8,
+        new_state: u8,
+    ) -> Result<()> {
+        let chunk = &mut ctx.accounts.chunk;
+        chunk.compare_and_set(index, expected, new_state)?;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.record_transition(expected, new_state);
+
+        Ok(())
+    }
+
     // =========================================================================
     // Merkle Instructions
     // =========================================================================
@@ -141,7 +310,7 @@ pub mod stratum {
         let merkle = &ctx.accounts.merkle_root;
         let is_valid = verify_proof(&proof, merkle.root, leaf, index);
 
-        emit!(MerkleProofVerified {
+        emit_event!(ctx, MerkleProofVerified {
             merkle_root: merkle.key(),
             leaf,
             index,
@@ -152,6 +321,370 @@ pub mod stratum {
         Ok(())
     }
 
+    // =========================================================================
+    // TTL Record Instructions
+    // =========================================================================
+
+    /// Create an expiring record. The payer funds rent plus `cleanup_reward`,
+    /// which is escrowed in the record until cleanup.
+    pub fn create_record(
+        ctx: Context<CreateRecord>,
+        record_id: u64,
+        data: Vec<u8>,
+        ttl: i64,
+        grace_period: i64,
+        cleanup_reward: u64,
+        unit: ExpiryUnit,
+    ) -> Result<()> {
+        require!(ttl > 0, StratumError::InvalidConfig);
+
+        let mut expiry = ExpiryConfig::new_at(unit.now()?, ttl, grace_period, cleanup_reward)?;
+        expiry.unit = unit;
+
+        let record = &mut ctx.accounts.record;
+        record.initialize(
+            ctx.accounts.authority.key(),
+            ctx.accounts.payer.key(),
+            record_id,
+            data,
+            expiry,
+            ctx.bumps.record,
+        )?;
+
+        if cleanup_reward > 0 {
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.payer.key(),
+                &record.key(),
+                cleanup_reward,
+            );
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.payer.to_account_info(),
+                    record.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Extend a record's expiry. The authority pays `reward_top_up` lamports
+    /// into the record, raising the escrowed cleanup reward.
+    pub fn extend_record(
+        ctx: Context<ExtendRecord>,
+        additional: i64,
+        reward_top_up: u64,
+    ) -> Result<()> {
+        let record = &mut ctx.accounts.record;
+        let now = record.expiry.unit.now()?;
+        record.extend_at(now, additional, reward_top_up)?;
+
+        if reward_top_up > 0 {
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.authority.key(),
+                &record.key(),
+                reward_top_up,
+            );
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.authority.to_account_info(),
+                    record.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Set or clear the record's paid renewal terms
+    pub fn set_record_lease(ctx: Context<ModifyRecord>, lease: Option<LeaseTerms>) -> Result<()> {
+        if let Some(terms) = &lease {
+            require!(terms.price_per_unit > 0, StratumError::InvalidConfig);
+        }
+        ctx.accounts.record.lease = lease;
+        Ok(())
+    }
+
+    /// Renew a record under its lease terms. The renewer pays
+    /// `price_per_unit * extension` (lamports or tokens) to the beneficiary.
+    pub fn renew_record(ctx: Context<RenewRecord>, extension: i64) -> Result<()> {
+        let record = &mut ctx.accounts.record;
+        let terms = record.lease.ok_or(StratumError::NoLease)?;
+        let now = record.expiry.unit.now()?;
+        let by_owner = ctx.accounts.renewer.key() == record.authority;
+        let price = terms.renew_at(&mut record.expiry, now, extension, by_owner)?;
+
+        require!(
+            ctx.accounts.beneficiary.key() == terms.beneficiary,
+            StratumError::Unauthorized
+        );
+
+        if terms.pays_in_lamports() {
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.renewer.key(),
+                &ctx.accounts.beneficiary.key(),
+                price,
+            );
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.renewer.to_account_info(),
+                    ctx.accounts.beneficiary.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        } else {
+            let (Some(from), Some(to), Some(token_program)) = (
+                &ctx.accounts.renewer_token_account,
+                &ctx.accounts.beneficiary_token_account,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(StratumError::InvalidTokenAccount);
+            };
+
+            let (mint, owner) = token_account_mint_and_owner(&to.to_account_info())?;
+            require!(
+                mint == terms.payment_mint && owner == terms.beneficiary,
+                StratumError::InvalidTokenAccount
+            );
+
+            transfer_tokens(
+                from.to_account_info(),
+                to.to_account_info(),
+                ctx.accounts.renewer.to_account_info(),
+                token_program.to_account_info(),
+                price,
+            )?;
+        }
+
+        emit_event!(ctx, LeaseRenewed {
+            record: record.key(),
+            renewer: ctx.accounts.renewer.key(),
+            new_expires_at: record.expiry.expires_at,
+            payment_mint: terms.payment_mint,
+            paid: price,
+        });
+
+        Ok(())
+    }
+
+    /// Close an expired record (permissionless). The cleaner is paid from the
+    /// authority's reward pool if one is given, otherwise from the escrowed
+    /// reward; the remaining lamports are returned to the payer.
+    pub fn cleanup_record(ctx: Context<CleanupRecord>) -> Result<()> {
+        let record = &ctx.accounts.record;
+        require_cleanup_allowed(&record.expiry)?;
+
+        let record_info = record.to_account_info();
+        let cleaner_info = ctx.accounts.cleaner.to_account_info();
+        let (reward, rent_returned) = match ctx.accounts.reward_pool.as_mut() {
+            Some(reward_pool) => {
+                let paid = pay_from_pool(reward_pool, &cleaner_info, record.expiry.cleanup_reward)?;
+                (paid, record_info.lamports())
+            }
+            None => {
+                let (reward, rent_returned) = record.cleanup_split(record_info.lamports());
+                if reward > 0 {
+                    **record_info.try_borrow_mut_lamports()? -= reward;
+                    **cleaner_info.try_borrow_mut_lamports()? += reward;
+                }
+                (reward, rent_returned)
+            }
+        };
+
+        emit_event!(ctx, CleanupReceipt {
+            cleaned_account: record.key(),
+            cleaner: ctx.accounts.cleaner.key(),
+            cleaned_at: record.expiry.unit.now()?,
+            reward_paid: reward,
+            rent_returned,
+        });
+
+        // Close sends the remaining lamports to the payer
+        Ok(())
+    }
+
+    // =========================================================================
+    // Expiry Index Instructions
+    // =========================================================================
+
+    /// Create an expiry index holding up to `capacity` items
+    pub fn create_expiry_index(
+        ctx: Context<CreateExpiryIndex>,
+        _index_id: u64,
+        capacity: u16,
+        unit: ExpiryUnit,
+    ) -> Result<()> {
+        let index = &mut ctx.accounts.expiry_index;
+        index.initialize(
+            ctx.accounts.authority.key(),
+            unit,
+            capacity,
+            ctx.bumps.expiry_index,
+        )
+    }
+
+    /// Register an item's cleanup time (expires_at + grace_period)
+    pub fn register_expiry(ctx: Context<ModifyExpiryIndex>, item: Pubkey, due_at: i64) -> Result<()> {
+        ctx.accounts.expiry_index.insert(item, due_at)
+    }
+
+    /// Remove an item from the index (after cleanup or extension)
+    pub fn remove_expiry(ctx: Context<ModifyExpiryIndex>, item: Pubkey) -> Result<()> {
+        ctx.accounts.expiry_index.remove(&item)?;
+        Ok(())
+    }
+
+    /// Items whose cleanup time has passed, earliest first.
+    /// Returned via return data; `limit` is capped at `ExpiryIndex::MAX_DUE_QUERY`.
+    pub fn get_due_items(ctx: Context<ReadExpiryIndex>, limit: u8) -> Result<Vec<Pubkey>> {
+        let index = &ctx.accounts.expiry_index;
+        let limit = limit.min(ExpiryIndex::MAX_DUE_QUERY) as usize;
+        let now = index.unit.now()?;
+
+        Ok(index.due(now, limit).iter().map(|e| e.item).collect())
+    }
+
+    // =========================================================================
+    // Reward Pool Instructions
+    // =========================================================================
+
+    /// Create a reward pool whose payouts must be signed by `consumer`
+    pub fn create_reward_pool(ctx: Context<CreateRewardPool>, consumer: Pubkey) -> Result<()> {
+        let pool = &mut ctx.accounts.reward_pool;
+        pool.initialize(ctx.accounts.authority.key(), consumer, ctx.bumps.reward_pool);
+        Ok(())
+    }
+
+    /// Deposit lamports into a reward pool (anyone can fund)
+    pub fn fund_reward_pool(ctx: Context<FundRewardPool>, amount: u64) -> Result<()> {
+        require!(amount > 0, StratumError::InvalidConfig);
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.funder.key(),
+            &ctx.accounts.reward_pool.key(),
+            amount,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.funder.to_account_info(),
+                ctx.accounts.reward_pool.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        ctx.accounts.reward_pool.record_funding(amount)
+    }
+
+    /// Withdraw lamports above the rent-exempt minimum back to the authority
+    pub fn withdraw_reward_pool(ctx: Context<WithdrawRewardPool>, amount: u64) -> Result<()> {
+        let pool_info = ctx.accounts.reward_pool.to_account_info();
+        require!(
+            amount <= lamports_above_rent(&pool_info)?,
+            StratumError::InsufficientPoolBalance
+        );
+
+        **pool_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        Ok(())
+    }
+
+    /// Pay a cleanup reward from the pool to `recipient`.
+    /// Must be signed by the pool's consumer (usually a PDA via CPI). The
+    /// payout is capped at the balance above rent exemption; the amount
+    /// actually paid is returned via return data.
+    pub fn pay_cleanup_reward(ctx: Context<PayCleanupReward>, amount: u64) -> Result<u64> {
+        let paid = pay_from_pool(
+            &mut ctx.accounts.reward_pool,
+            &ctx.accounts.recipient.to_account_info(),
+            amount,
+        )?;
+
+        emit!(CleanupRewardPaid {
+            reward_pool: ctx.accounts.reward_pool.key(),
+            recipient: ctx.accounts.recipient.key(),
+            requested: amount,
+            paid,
+        });
+
+        Ok(paid)
+    }
+
+    // =========================================================================
+    // Event Checkpoint Instructions
+    // =========================================================================
+
+    /// Create a standalone event checkpoint
+    pub fn create_event_checkpoint(
+        ctx: Context<CreateEventCheckpoint>,
+        _checkpoint_id: u64,
+    ) -> Result<()> {
+        let checkpoint = &mut ctx.accounts.event_checkpoint;
+        checkpoint.authority = ctx.accounts.authority.key();
+        checkpoint.accumulator = EventAccumulator::default();
+        checkpoint.bump = ctx.bumps.event_checkpoint;
+        Ok(())
+    }
+
+    /// Append an event hash (`ArchivableEvent::compute_hash`) to the checkpoint.
+    /// Fails once the epoch is full; close it first.
+    pub fn record_event_hash(ctx: Context<ModifyEventCheckpoint>, event_hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.event_checkpoint.accumulator.append(event_hash)?;
+        Ok(())
+    }
+
+    /// Close the current (non-empty) epoch, persisting its root in a
+    /// `CheckpointEpoch` account
+    pub fn close_checkpoint_epoch(ctx: Context<CloseCheckpointEpoch>) -> Result<()> {
+        let checkpoint = &mut ctx.accounts.event_checkpoint;
+        let closed = checkpoint.accumulator.close_epoch()?;
+
+        let checkpoint_epoch = &mut ctx.accounts.checkpoint_epoch;
+        checkpoint_epoch.checkpoint = checkpoint.key();
+        checkpoint_epoch.root = closed;
+        checkpoint_epoch.bump = ctx.bumps.checkpoint_epoch;
+
+        emit_event!(ctx, EventEpochClosed {
+            checkpoint: checkpoint.key(),
+            epoch: closed.epoch,
+            root: closed.root,
+            event_count: closed.event_count,
+        });
+        Ok(())
+    }
+
+    /// Verify that an event hash was checkpointed (view function, emits result).
+    /// Closed epochs need their `CheckpointEpoch` account.
+    pub fn verify_checkpointed_event(
+        ctx: Context<VerifyCheckpointedEvent>,
+        epoch: u64,
+        event_hash: [u8; 32],
+        proof: MerkleProof,
+    ) -> Result<()> {
+        let checkpoint = &ctx.accounts.event_checkpoint;
+        let is_valid = match &ctx.accounts.checkpoint_epoch {
+            Some(closed) => closed.root.epoch == epoch && closed.root.verify(event_hash, &proof),
+            None => checkpoint.accumulator.verify(epoch, event_hash, &proof),
+        };
+
+        emit_event!(ctx, CheckpointProofVerified {
+            checkpoint: checkpoint.key(),
+            epoch,
+            event_hash,
+            index: proof.leaf_index,
+            is_valid,
+        });
+
+        require!(is_valid, StratumError::InvalidMerkleProof);
+        Ok(())
+    }
+
     // =========================================================================
     // Archive/Resurrection Instructions
     // =========================================================================
@@ -185,6 +718,424 @@ pub mod stratum {
         let registry = &mut ctx.accounts.archive_registry;
         registry.finalize()
     }
+
+    /// Enable or disable resurrection, set who may resurrect and the fee
+    /// (in lamports) resurrectors pay the registry authority
+    pub fn configure_resurrection(
+        ctx: Context<UpdateArchiveRoot>,
+        enabled: bool,
+        policy: ResurrectionPolicy,
+        fee: u64,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.archive_registry;
+        registry.configure_resurrection(enabled, policy, fee)
+    }
+
+    /// Commit the next `leaf_count` archive indexes as a new segment with
+    /// its own frozen root. Once segments are used the registry root can
+    /// no longer be updated, so existing proofs never go stale.
+    pub fn commit_archive_segment(
+        ctx: Context<CommitArchiveSegment>,
+        merkle_root: [u8; 32],
+        leaf_count: u64,
+        da_commitment: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let archive_registry = &mut ctx.accounts.archive_registry;
+        let (segment_index, start_index) = archive_registry.commit_segment_at(now, leaf_count)?;
+
+        let segment = &mut ctx.accounts.segment;
+        segment.archive_registry = archive_registry.key();
+        segment.segment_index = segment_index;
+        segment.start_index = start_index;
+        segment.leaf_count = leaf_count;
+        segment.merkle_root = merkle_root;
+        segment.da_commitment = da_commitment;
+        segment.committed_at = now;
+        segment.bump = ctx.bumps.segment;
+        segment.leaf_version = LeafVersion::WithOwnerProgram;
+
+        emit_event!(ctx, ArchiveSegmentCommitted {
+            registry: archive_registry.key(),
+            segment: segment.key(),
+            segment_index,
+            start_index,
+            leaf_count,
+            merkle_root,
+            da_commitment,
+            leaf_version: LeafVersion::WithOwnerProgram,
+        });
+
+        Ok(())
+    }
+
+    /// Archive an account: hash its data into an archive leaf and append it
+    /// to the registry's pending leaves. The leaf is committed by the next
+    /// `update_archive_root` or `commit_archive_segment`; the caller closes
+    /// the account itself.
+    /// The archive index is returned via return data.
+    pub fn archive_record(ctx: Context<ArchiveRecord>, owner: Pubkey) -> Result<u64> {
+        let archive_registry = &mut ctx.accounts.archive_registry;
+        let archive_index = archive_registry.append_pending()?;
+
+        let record = ArchivedRecord::new(
+            ctx.accounts.archived_account.key(),
+            owner,
+            *ctx.accounts.archived_account.owner,
+            archive_index,
+            &ctx.accounts.archived_account.try_borrow_data()?,
+        )?;
+
+        let registry = archive_registry.key();
+        emit_event!(ctx, RecordArchived {
+            registry,
+            account: record.account,
+            owner: record.owner,
+            archive_index,
+            data_hash: record.data_hash,
+        });
+        emit_event!(ctx, RecordOwnerProgram {
+            registry,
+            archive_index,
+            owner_program: record.owner_program,
+        });
+
+        Ok(archive_index)
+    }
+
+    /// Resurrect an archived record: verify its proof against the archive
+    /// root (or the segment the proof names, passed as `segment`) and set
+    /// its bit in the archive's tracking chunk so it can't be resurrected
+    /// twice. The resurrector must be allowed by the registry's policy and
+    /// pays its resurrection fee.
+    pub fn resurrect_record(
+        ctx: Context<ResurrectRecord>,
+        record: ArchivedRecord,
+        proof: ResurrectionProof,
+    ) -> Result<()> {
+        resurrect(
+            &mut ctx.accounts.archive_registry,
+            &mut ctx.accounts.chunk,
+            ctx.bumps.chunk,
+            ctx.accounts.segment.as_deref(),
+            &record,
+            &proof,
+        )?;
+        authorize_resurrector(
+            &ctx.accounts.archive_registry,
+            &record,
+            &ctx.accounts.resurrector,
+            &ctx.accounts.registry_authority,
+            &ctx.accounts.system_program,
+        )?;
+
+        emit_event!(ctx, RecordResurrected {
+            registry: ctx.accounts.archive_registry.key(),
+            account: record.account,
+            owner: record.owner,
+            archive_index: record.archive_index,
+            resurrected_by: ctx.accounts.resurrector.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Resurrect an archived record and re-create its account at the
+    /// original address with the original data (checked against
+    /// `record.data_hash`). The resurrector pays rent and the registry's
+    /// resurrection fee, and must be allowed by its policy.
+    ///
+    /// Stratum's own `TtlRecord`s are restored directly, with the
+    /// resurrector as their new payer, if the record's authority is the
+    /// registry's. Accounts of other programs are re-created by the program
+    /// that owned them (`record.owner_program`) through its
+    /// `restore_archived` callback (see `RestoreArgs`), which receives the
+    /// remaining accounts.
+    /// `expiry` optionally gives the restored account a fresh expiry so it
+    /// can be archived again later.
+    pub fn restore_record<'info>(
+        ctx: Context<'_, '_, '_, 'info, RestoreRecord<'info>>,
+        record: ArchivedRecord,
+        proof: ResurrectionProof,
+        data: Vec<u8>,
+        expiry: Option<ExpiryConfig>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.restored_account.key() == record.account,
+            StratumError::InvalidResurrectionProof
+        );
+        verify_restored_data(&record, &data)?;
+        resurrect(
+            &mut ctx.accounts.archive_registry,
+            &mut ctx.accounts.chunk,
+            ctx.bumps.chunk,
+            ctx.accounts.segment.as_deref(),
+            &record,
+            &proof,
+        )?;
+        authorize_resurrector(
+            &ctx.accounts.archive_registry,
+            &record,
+            &ctx.accounts.resurrector,
+            &ctx.accounts.registry_authority,
+            &ctx.accounts.system_program,
+        )?;
+
+        let owner_program = ctx.accounts.owner_program.key();
+        if owner_program == crate::ID {
+            restore_ttl_record(&ctx, &record, &data, expiry)?;
+        } else {
+            // Legacy leaves don't commit the owner program, so only Stratum's
+            // own records (whose address pins the program) restore from them
+            let leaf_version = ctx.accounts.segment.as_ref().map_or(LeafVersion::Legacy, |s| s.leaf_version);
+            require!(
+                leaf_version == LeafVersion::WithOwnerProgram,
+                StratumError::InvalidResurrectionProof
+            );
+            let remaining: Vec<AccountMeta> = ctx
+                .remaining_accounts
+                .iter()
+                .map(|info| AccountMeta {
+                    pubkey: info.key(),
+                    is_signer: info.is_signer,
+                    is_writable: info.is_writable,
+                })
+                .collect();
+            let callback = restore_callback_ix(
+                owner_program,
+                ctx.accounts.archive_registry.key(),
+                record.account,
+                ctx.accounts.resurrector.key(),
+                &remaining,
+                &RestoreArgs {
+                    record: record.clone(),
+                    data,
+                    expiry,
+                },
+            )?;
+
+            let mut infos = vec![
+                ctx.accounts.restore_authority.to_account_info(),
+                ctx.accounts.archive_registry.to_account_info(),
+                ctx.accounts.restored_account.to_account_info(),
+                ctx.accounts.resurrector.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.owner_program.to_account_info(),
+            ];
+            infos.extend(ctx.remaining_accounts.iter().cloned());
+            let archive_registry = ctx.accounts.archive_registry.key();
+            anchor_lang::solana_program::program::invoke_signed(
+                &callback,
+                &infos,
+                &[&[
+                    RESTORE_AUTHORITY_SEED,
+                    archive_registry.as_ref(),
+                    &[ctx.bumps.restore_authority],
+                ]],
+            )?;
+
+            let restored = &ctx.accounts.restored_account;
+            require!(
+                restored.owner == &owner_program && !restored.data_is_empty(),
+                StratumError::InvalidResurrectionProof
+            );
+        }
+
+        emit_event!(ctx, RecordResurrected {
+            registry: ctx.accounts.archive_registry.key(),
+            account: record.account,
+            owner: record.owner,
+            archive_index: record.archive_index,
+            resurrected_by: ctx.accounts.resurrector.key(),
+        });
+
+        Ok(())
+    }
+
+    // =========================================================================
+    // Migration Instructions
+    // =========================================================================
+
+    /// Migrate an archive registry created before `HistorySummary::sequence`
+    /// and the segment and resurrection policy fields to the current layout
+    /// (authority only, who pays the extra rent)
+    ///
+    /// Current registries are left unchanged.
+    pub fn migrate_archive_registry(ctx: Context<MigrateAccount>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+
+        migrate_account(
+            &ctx.accounts.account.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            ArchiveRegistry::LEGACY_SPACE,
+            8 + ArchiveRegistry::INIT_SPACE,
+            |legacy: LegacyArchiveRegistry| {
+                require_keys_eq!(legacy.authority, authority, StratumError::Unauthorized);
+                Ok(ArchiveRegistry::from(legacy))
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Migrate a bitfield registry created before the allocation hint and
+    /// generations to the current layout (authority only, who pays the
+    /// extra rent). Current registries are left unchanged.
+    pub fn migrate_bitfield_registry(ctx: Context<MigrateAccount>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+
+        migrate_account(
+            &ctx.accounts.account.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            BitfieldRegistry::LEGACY_SPACE,
+            8 + BitfieldRegistry::INIT_SPACE,
+            |legacy: LegacyBitfieldRegistry| {
+                require_keys_eq!(legacy.authority, authority, StratumError::Unauthorized);
+                Ok(BitfieldRegistry::from(legacy))
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Migrate a bitfield chunk created before generations and freezing to
+    /// the current layout, as generation 0 (authority only, who pays the
+    /// extra rent). Current chunks are left unchanged.
+    pub fn migrate_bitfield_chunk(ctx: Context<MigrateAccount>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+
+        migrate_account(
+            &ctx.accounts.account.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            BitfieldChunk::LEGACY_SPACE,
+            8 + BitfieldChunk::INIT_SPACE,
+            |legacy: LegacyBitfieldChunk| {
+                require_keys_eq!(legacy.authority, authority, StratumError::Unauthorized);
+                Ok(BitfieldChunk::from(legacy))
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Verify a resurrection and mark it in the archive's tracking chunk,
+/// initializing the chunk if it was just created
+fn resurrect(
+    archive_registry: &mut Account<ArchiveRegistry>,
+    chunk: &mut BitfieldChunk,
+    chunk_bump: u8,
+    segment: Option<&ArchiveSegment>,
+    record: &ArchivedRecord,
+    proof: &ResurrectionProof,
+) -> Result<()> {
+    let archive_key = archive_registry.key();
+    if chunk.registry == Pubkey::default() {
+        // Owned by the archive: no bitfield registry authority can touch it
+        chunk.initialize(archive_key, archive_key, record.tracking_chunk(), 0, chunk_bump);
+    }
+
+    verify_record_resurrection(archive_registry, &archive_key, segment, chunk, record, proof)?;
+    mark_record_resurrected(archive_registry, chunk, record)
+}
+
+/// Check the registry's policy allows the resurrector to resurrect the
+/// (verified) record and transfer its resurrection fee to the authority
+fn authorize_resurrector<'info>(
+    archive_registry: &ArchiveRegistry,
+    record: &ArchivedRecord,
+    resurrector: &Signer<'info>,
+    registry_authority: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    archive_registry.authorize_resurrection(record, &resurrector.key(), Clock::get()?.slot)?;
+
+    let fee = archive_registry.resurrection_fee;
+    if fee == 0 {
+        return Ok(());
+    }
+
+    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+        &resurrector.key(),
+        &registry_authority.key(),
+        fee,
+    );
+    anchor_lang::solana_program::program::invoke(
+        &transfer_ix,
+        &[
+            resurrector.to_account_info(),
+            registry_authority.to_account_info(),
+            system_program.to_account_info(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Re-create an archived `TtlRecord` at its PDA
+fn restore_ttl_record(
+    ctx: &Context<RestoreRecord>,
+    record: &ArchivedRecord,
+    data: &[u8],
+    expiry: Option<ExpiryConfig>,
+) -> Result<()> {
+    let restored = TtlRecord::restored(data, ctx.accounts.resurrector.key(), expiry)?;
+    // Anyone can archive into their own registry: only the record's
+    // authority may bring it back
+    require!(
+        ctx.accounts.archive_registry.authority == restored.authority,
+        StratumError::Unauthorized
+    );
+
+    let record_id = restored.record_id.to_le_bytes();
+    let seeds: &[&[u8]] = &[
+        b"ttl_record",
+        restored.authority.as_ref(),
+        &record_id,
+        &[restored.bump],
+    ];
+    let address = Pubkey::create_program_address(seeds, &crate::ID)
+        .map_err(|_| error!(StratumError::InvalidResurrectionProof))?;
+    require!(
+        address == record.account,
+        StratumError::InvalidResurrectionProof
+    );
+
+    // Same length as the archived data: only fixed-size fields change
+    let mut restored_data = data.to_vec();
+    restored.try_serialize(&mut restored_data.as_mut_slice())?;
+
+    create_restored_account(
+        &ctx.accounts.resurrector.to_account_info(),
+        &ctx.accounts.restored_account.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        &crate::ID,
+        &restored_data,
+        restored.expiry.cleanup_reward,
+        &[seeds],
+    )
+}
+
+/// Pay up to `amount` from a reward pool to `recipient`, keeping the pool
+/// rent exempt. Returns the amount actually paid.
+fn pay_from_pool<'info>(
+    reward_pool: &mut Account<'info, RewardPool>,
+    recipient: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let pool_info = reward_pool.to_account_info();
+    let rent_floor = Rent::get()?.minimum_balance(pool_info.data_len());
+    let paid = RewardPool::payable(amount, pool_info.lamports(), rent_floor);
+
+    if paid > 0 {
+        **pool_info.try_borrow_mut_lamports()? -= paid;
+        **recipient.try_borrow_mut_lamports()? += paid;
+        reward_pool.record_payout(paid);
+    }
+
+    Ok(paid)
 }
 
 // =============================================================================
@@ -228,6 +1179,14 @@ pub struct CreateBitfieldChunk<'info> {
     )]
     pub chunk: Account<'info, BitfieldChunk>,
 
+    /// CHECK: tombstone PDA of the chunk index; must not exist
+    #[account(
+        seeds = [b"archived_chunk", registry.key().as_ref(), &chunk_index.to_le_bytes()],
+        bump,
+        constraint = tombstone.data_is_empty() @ StratumError::ChunkArchived
+    )]
+    pub tombstone: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 
@@ -255,11 +1214,131 @@ pub struct ModifyBitfield<'info> {
     pub authority: Signer<'info>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
-#[instruction(seed: u64)]
-pub struct CreateMerkleRoot<'info> {
+pub struct ArchiveBitfieldChunk<'info> {
     #[account(
-        init,
+        mut,
+        seeds = [b"bitfield_registry", authority.key().as_ref()],
+        bump = registry.bump,
+        constraint = registry.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub registry: Account<'info, BitfieldRegistry>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"bitfield_chunk", registry.key().as_ref(), &chunk.chunk_index.to_le_bytes()],
+        bump = chunk.bump,
+        constraint = chunk.registry == registry.key() @ StratumError::Unauthorized
+    )]
+    pub chunk: Account<'info, BitfieldChunk>,
+
+    #[account(
+        mut,
+        constraint = archive_registry.authority == authority.key() @ StratumError::Unauthorized,
+        constraint = archive_registry.bitfield_registry == registry.key() @ StratumError::WrongBitfieldChunk
+    )]
+    pub archive_registry: Account<'info, ArchiveRegistry>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ArchivedChunk::INIT_SPACE,
+        seeds = [b"archived_chunk", registry.key().as_ref(), &chunk.chunk_index.to_le_bytes()],
+        bump
+    )]
+    pub tombstone: Account<'info, ArchivedChunk>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct RollBitfieldGeneration<'info> {
+    #[account(
+        mut,
+        seeds = [b"bitfield_registry", authority.key().as_ref()],
+        bump = registry.bump,
+        constraint = registry.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub registry: Account<'info, BitfieldRegistry>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateStateFieldRegistry<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + StateFieldRegistry::INIT_SPACE,
+        seeds = [b"state_field_registry", authority.key().as_ref()],
+        bump
+    )]
+    pub registry: Account<'info, StateFieldRegistry>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chunk_index: u32)]
+pub struct CreateStateFieldChunk<'info> {
+    #[account(
+        mut,
+        seeds = [b"state_field_registry", authority.key().as_ref()],
+        bump = registry.bump,
+        constraint = registry.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub registry: Account<'info, StateFieldRegistry>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = StateFieldChunk::space(registry.bits_per_entry),
+        seeds = [b"state_field_chunk", registry.key().as_ref(), &chunk_index.to_le_bytes()],
+        bump
+    )]
+    pub chunk: Account<'info, StateFieldChunk>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ModifyStateField<'info> {
+    #[account(
+        mut,
+        seeds = [b"state_field_registry", authority.key().as_ref()],
+        bump = registry.bump,
+        constraint = registry.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub registry: Account<'info, StateFieldRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"state_field_chunk", registry.key().as_ref(), &chunk.chunk_index.to_le_bytes()],
+        bump = chunk.bump,
+        constraint = chunk.registry == registry.key() @ StratumError::Unauthorized
+    )]
+    pub chunk: Account<'info, StateFieldChunk>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateMerkleRoot<'info> {
+    #[account(
+        init,
         payer = authority,
         space = 8 + MerkleRoot::INIT_SPACE,
         seeds = [b"merkle_root", authority.key().as_ref(), &seed.to_le_bytes()],
@@ -284,11 +1363,304 @@ pub struct UpdateMerkleRoot<'info> {
     pub authority: Signer<'info>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
 pub struct VerifyMerkleProof<'info> {
     pub merkle_root: Account<'info, MerkleRoot>,
 }
 
+#[derive(Accounts)]
+#[instruction(record_id: u64, data: Vec<u8>)]
+pub struct CreateRecord<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = TtlRecord::space(data.len()),
+        seeds = [b"ttl_record", authority.key().as_ref(), &record_id.to_le_bytes()],
+        bump
+    )]
+    pub record: Account<'info, TtlRecord>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendRecord<'info> {
+    #[account(
+        mut,
+        seeds = [b"ttl_record", authority.key().as_ref(), &record.record_id.to_le_bytes()],
+        bump = record.bump,
+        constraint = record.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub record: Account<'info, TtlRecord>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ModifyRecord<'info> {
+    #[account(
+        mut,
+        seeds = [b"ttl_record", authority.key().as_ref(), &record.record_id.to_le_bytes()],
+        bump = record.bump,
+        constraint = record.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub record: Account<'info, TtlRecord>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct RenewRecord<'info> {
+    #[account(
+        mut,
+        seeds = [b"ttl_record", record.authority.as_ref(), &record.record_id.to_le_bytes()],
+        bump = record.bump
+    )]
+    pub record: Account<'info, TtlRecord>,
+
+    /// Anyone can renew open leases; owner-only leases require the authority
+    #[account(mut)]
+    pub renewer: Signer<'info>,
+
+    /// CHECK: Verified against the lease beneficiary in the handler
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+
+    /// CHECK: Renewer's token account (token-priced leases only)
+    #[account(mut)]
+    pub renewer_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Beneficiary's token account, mint and owner checked in the handler
+    #[account(mut)]
+    pub beneficiary_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Token program
+    #[account(address = spl_token::ID)]
+    pub token_program: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CleanupRecord<'info> {
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"ttl_record", record.authority.as_ref(), &record.record_id.to_le_bytes()],
+        bump = record.bump,
+        constraint = record.payer == payer.key() @ StratumError::Unauthorized
+    )]
+    pub record: Account<'info, TtlRecord>,
+
+    /// CHECK: Original payer, verified against record.payer; receives remaining rent
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+
+    /// Anyone can clean up an expired record
+    #[account(mut)]
+    pub cleaner: Signer<'info>,
+
+    /// Reward pool bound to the record's authority. When given, it pays the
+    /// cleaner and the escrowed reward goes back to the payer.
+    #[account(
+        mut,
+        seeds = [b"reward_pool", reward_pool.authority.as_ref(), record.authority.as_ref()],
+        bump = reward_pool.bump,
+        constraint = reward_pool.consumer == record.authority @ StratumError::Unauthorized
+    )]
+    pub reward_pool: Option<Account<'info, RewardPool>>,
+}
+
+#[derive(Accounts)]
+#[instruction(index_id: u64, capacity: u16)]
+pub struct CreateExpiryIndex<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = ExpiryIndex::space(capacity),
+        seeds = [b"expiry_index", authority.key().as_ref(), &index_id.to_le_bytes()],
+        bump
+    )]
+    pub expiry_index: Account<'info, ExpiryIndex>,
+
+    /// Usually a program PDA signing via CPI, so it doesn't fund the index
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ModifyExpiryIndex<'info> {
+    #[account(
+        mut,
+        constraint = expiry_index.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub expiry_index: Account<'info, ExpiryIndex>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadExpiryIndex<'info> {
+    pub expiry_index: Account<'info, ExpiryIndex>,
+}
+
+#[derive(Accounts)]
+#[instruction(consumer: Pubkey)]
+pub struct CreateRewardPool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RewardPool::INIT_SPACE,
+        seeds = [b"reward_pool", authority.key().as_ref(), consumer.as_ref()],
+        bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewardPool<'info> {
+    #[account(
+        mut,
+        seeds = [b"reward_pool", reward_pool.authority.as_ref(), reward_pool.consumer.as_ref()],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawRewardPool<'info> {
+    #[account(
+        mut,
+        seeds = [b"reward_pool", authority.key().as_ref(), reward_pool.consumer.as_ref()],
+        bump = reward_pool.bump,
+        constraint = reward_pool.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PayCleanupReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"reward_pool", reward_pool.authority.as_ref(), consumer.key().as_ref()],
+        bump = reward_pool.bump,
+        constraint = reward_pool.consumer == consumer.key() @ StratumError::Unauthorized
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    /// Consumer bound to the pool (PDAs sign via CPI)
+    pub consumer: Signer<'info>,
+
+    /// CHECK: Any account can receive the reward
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(checkpoint_id: u64)]
+pub struct CreateEventCheckpoint<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EventCheckpoint::INIT_SPACE,
+        seeds = [b"event_checkpoint", authority.key().as_ref(), &checkpoint_id.to_le_bytes()],
+        bump
+    )]
+    pub event_checkpoint: Account<'info, EventCheckpoint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ModifyEventCheckpoint<'info> {
+    #[account(
+        mut,
+        constraint = event_checkpoint.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub event_checkpoint: Account<'info, EventCheckpoint>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CloseCheckpointEpoch<'info> {
+    #[account(
+        mut,
+        constraint = event_checkpoint.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub event_checkpoint: Account<'info, EventCheckpoint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CheckpointEpoch::INIT_SPACE,
+        seeds = [
+            CHECKPOINT_EPOCH_SEED,
+            event_checkpoint.key().as_ref(),
+            &event_checkpoint.accumulator.epoch.to_le_bytes()
+        ],
+        bump
+    )]
+    pub checkpoint_epoch: Account<'info, CheckpointEpoch>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct VerifyCheckpointedEvent<'info> {
+    pub event_checkpoint: Account<'info, EventCheckpoint>,
+
+    /// Root of the epoch being checked, if it is closed
+    #[account(
+        seeds = [
+            CHECKPOINT_EPOCH_SEED,
+            event_checkpoint.key().as_ref(),
+            &checkpoint_epoch.root.epoch.to_le_bytes()
+        ],
+        bump = checkpoint_epoch.bump,
+        constraint = checkpoint_epoch.checkpoint == event_checkpoint.key() @ StratumError::InvalidMerkleProof
+    )]
+    pub checkpoint_epoch: Option<Account<'info, CheckpointEpoch>>,
+}
+
 #[derive(Accounts)]
 #[instruction(name: String)]
 pub struct CreateArchiveRegistry<'info> {
@@ -320,6 +1692,144 @@ pub struct UpdateArchiveRoot<'info> {
     pub authority: Signer<'info>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CommitArchiveSegment<'info> {
+    #[account(
+        mut,
+        constraint = archive_registry.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub archive_registry: Account<'info, ArchiveRegistry>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ArchiveSegment::INIT_SPACE,
+        seeds = [b"archive_segment", archive_registry.key().as_ref(), &archive_registry.segment_count.to_le_bytes()],
+        bump
+    )]
+    pub segment: Account<'info, ArchiveSegment>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ArchiveRecord<'info> {
+    #[account(
+        mut,
+        constraint = archive_registry.authority == authority.key() @ StratumError::Unauthorized
+    )]
+    pub archive_registry: Account<'info, ArchiveRegistry>,
+
+    /// CHECK: only read; its address and data are hashed into the archive leaf
+    pub archived_account: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(record: ArchivedRecord)]
+pub struct ResurrectRecord<'info> {
+    #[account(mut)]
+    pub archive_registry: Account<'info, ArchiveRegistry>,
+
+    /// The archive's resurrection-tracking chunk, created on first use
+    #[account(
+        init_if_needed,
+        payer = resurrector,
+        space = 8 + BitfieldChunk::INIT_SPACE,
+        seeds = [RESURRECTION_CHUNK_SEED, archive_registry.key().as_ref(), &record.tracking_chunk().to_le_bytes()],
+        bump,
+        constraint = record.archive_index < archive_registry.archived_count @ StratumError::LeafIndexOutOfRange
+    )]
+    pub chunk: Account<'info, BitfieldChunk>,
+
+    #[account(mut)]
+    pub resurrector: Signer<'info>,
+
+    /// CHECK: registry authority, receives the resurrection fee
+    #[account(mut, address = archive_registry.authority @ StratumError::Unauthorized)]
+    pub registry_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Segment named by the proof (None for proofs against the registry root)
+    #[account(
+        seeds = [b"archive_segment", archive_registry.key().as_ref(), &segment.segment_index.to_le_bytes()],
+        bump = segment.bump,
+        constraint = segment.archive_registry == archive_registry.key() @ StratumError::InvalidResurrectionProof
+    )]
+    pub segment: Option<Account<'info, ArchiveSegment>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(record: ArchivedRecord)]
+pub struct RestoreRecord<'info> {
+    #[account(mut)]
+    pub archive_registry: Account<'info, ArchiveRegistry>,
+
+    /// The archive's resurrection-tracking chunk, created on first use
+    #[account(
+        init_if_needed,
+        payer = resurrector,
+        space = 8 + BitfieldChunk::INIT_SPACE,
+        seeds = [RESURRECTION_CHUNK_SEED, archive_registry.key().as_ref(), &record.tracking_chunk().to_le_bytes()],
+        bump,
+        constraint = record.archive_index < archive_registry.archived_count @ StratumError::LeafIndexOutOfRange
+    )]
+    pub chunk: Account<'info, BitfieldChunk>,
+
+    /// CHECK: the archived address, matched against the record and re-created here
+    #[account(mut)]
+    pub restored_account: UncheckedAccount<'info>,
+
+    /// CHECK: program that owned the archived account (Stratum itself for
+    /// `TtlRecord`s); it must re-create the account via `restore_archived`
+    #[account(executable, address = record.owner_program @ StratumError::InvalidResurrectionProof)]
+    pub owner_program: UncheckedAccount<'info>,
+
+    /// CHECK: PDA signing the restore callback for this archive registry
+    #[account(seeds = [RESTORE_AUTHORITY_SEED, archive_registry.key().as_ref()], bump)]
+    pub restore_authority: UncheckedAccount<'info>,
+
+    /// Pays rent for the restored account
+    #[account(mut)]
+    pub resurrector: Signer<'info>,
+
+    /// CHECK: registry authority, receives the resurrection fee
+    #[account(mut, address = archive_registry.authority @ StratumError::Unauthorized)]
+    pub registry_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Segment named by the proof (None for proofs against the registry root)
+    #[account(
+        seeds = [b"archive_segment", archive_registry.key().as_ref(), &segment.segment_index.to_le_bytes()],
+        bump = segment.bump,
+        constraint = segment.archive_registry == archive_registry.key() @ StratumError::InvalidResurrectionProof
+    )]
+    pub segment: Option<Account<'info, ArchiveSegment>>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Owner, discriminator and layout are checked by `migrate_account`
+    #[account(mut)]
+    pub account: UncheckedAccount<'info>,
+
+    /// The account's authority, pays rent for the added bytes
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 // =============================================================================
 // Events
 // =============================================================================
@@ -345,3 +1855,44 @@ pub struct BitUnset {
     pub chunk: Pubkey,
     pub global_index: u32,
 }
+
+#[event]
+pub struct BitfieldGenerationRolled {
+    pub registry: Pubkey,
+    pub generation: u32,
+    pub previous_total_set: u64,
+}
+
+#[event]
+pub struct CleanupRewardPaid {
+    pub reward_pool: Pubkey,
+    pub recipient: Pubkey,
+    pub requested: u64,
+    pub paid: u64,
+}
+
+#[event]
+pub struct LeaseRenewed {
+    pub record: Pubkey,
+    pub renewer: Pubkey,
+    pub new_expires_at: i64,
+    pub payment_mint: Pubkey,
+    pub paid: u64,
+}
+
+#[event]
+pub struct EventEpochClosed {
+    pub checkpoint: Pubkey,
+    pub epoch: u64,
+    pub root: [u8; 32],
+    pub event_count: u64,
+}
+
+#[event]
+pub struct CheckpointProofVerified {
+    pub checkpoint: Pubkey,
+    pub epoch: u64,
+    pub event_hash: [u8; 32],
+    pub index: u32,
+    pub is_valid: bool,
+}
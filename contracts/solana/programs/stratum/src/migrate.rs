@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+
+use crate::events::HistorySummary;
+use crate::expiry::{ExpiryConfig, ExpiryUnit};
+
+/// `ExpiryConfig` as laid out before `unit` was added
+///
+/// Every config of this layout was measured in unix timestamps.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LegacyExpiryConfig {
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub grace_period: i64,
+    pub cleanup_reward: u64,
+}
+
+impl LegacyExpiryConfig {
+    pub const SPACE: usize = 8 + 8 + 8 + 8;
+}
+
+impl From<LegacyExpiryConfig> for ExpiryConfig {
+    fn from(legacy: LegacyExpiryConfig) -> Self {
+        Self {
+            created_at: legacy.created_at,
+            expires_at: legacy.expires_at,
+            grace_period: legacy.grace_period,
+            cleanup_reward: legacy.cleanup_reward,
+            unit: ExpiryUnit::Timestamp,
+        }
+    }
+}
+
+/// `HistorySummary` as laid out before `sequence` was added
+///
+/// Its `last_event_hash` was set directly rather than chained, so the
+/// migrated summary keeps it as the chain's head at sequence 0: verify
+/// later events with `verify_chain_from(last_event_hash, 0, ..)`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LegacyHistorySummary {
+    pub total_count: u64,
+    pub total_value: u128,
+    pub min_value: u64,
+    pub max_value: u64,
+    pub last_slot: u64,
+    pub last_timestamp: i64,
+    pub last_event_hash: [u8; 32],
+}
+
+impl LegacyHistorySummary {
+    pub const SPACE: usize = 8 + 16 + 8 + 8 + 8 + 8 + 32;
+}
+
+impl From<LegacyHistorySummary> for HistorySummary {
+    fn from(legacy: LegacyHistorySummary) -> Self {
+        Self {
+            total_count: legacy.total_count,
+            total_value: legacy.total_value,
+            min_value: legacy.min_value,
+            max_value: legacy.max_value,
+            last_slot: legacy.last_slot,
+            last_timestamp: legacy.last_timestamp,
+            last_event_hash: legacy.last_event_hash,
+            sequence: 0,
+        }
+    }
+}
+
+/// Rewrite an account of type `T` stored in its legacy layout `L`
+///
+/// Layouts are told apart by length. An account `legacy_space` bytes long
+/// is deserialized as `L` (after `T`'s discriminator, which is unchanged),
+/// passed to `convert`, grown to `space` with rent topped up by `payer`
+/// and written back as `T`. An account already `space` bytes long is left
+/// alone, so migrating twice is a no-op.
+///
+/// Returns whether the account was migrated.
+pub fn migrate_account<'info, L, T>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    legacy_space: usize,
+    space: usize,
+    convert: impl FnOnce(L) -> Result<T>,
+) -> Result<bool>
+where
+    L: AnchorDeserialize,
+    T: AccountSerialize + Discriminator + Owner,
+{
+    require_keys_eq!(*account.owner, T::owner(), ErrorCode::AccountOwnedByWrongProgram);
+
+    let len = account.data_len();
+    if len == space {
+        return Ok(false);
+    }
+    require_eq!(len, legacy_space, ErrorCode::AccountDidNotDeserialize);
+
+    let legacy = {
+        let data = account.try_borrow_data()?;
+        require!(
+            data.starts_with(T::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        L::deserialize(&mut &data[T::DISCRIMINATOR.len()..])
+            .map_err(|_| ErrorCode::AccountDidNotDeserialize)?
+    };
+    let migrated = convert(legacy)?;
+
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.resize(space)?;
+
+    let mut data = account.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    migrated.try_serialize(&mut writer)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_spaces() {
+        let expiry = LegacyExpiryConfig::default().try_to_vec().unwrap();
+        assert_eq!(expiry.len(), LegacyExpiryConfig::SPACE);
+        assert_eq!(LegacyExpiryConfig::SPACE + 1, ExpiryConfig::INIT_SPACE);
+
+        let history = LegacyHistorySummary::default().try_to_vec().unwrap();
+        assert_eq!(history.len(), LegacyHistorySummary::SPACE);
+        assert_eq!(LegacyHistorySummary::SPACE + 8, HistorySummary::INIT_SPACE);
+    }
+
+    #[test]
+    fn test_legacy_conversions() {
+        let expiry = ExpiryConfig::from(LegacyExpiryConfig {
+            created_at: 100,
+            expires_at: 200,
+            grace_period: 10,
+            cleanup_reward: 5,
+        });
+        assert_eq!(expiry.unit, ExpiryUnit::Timestamp);
+        assert_eq!(expiry.expires_at, 200);
+
+        let history = HistorySummary::from(LegacyHistorySummary {
+            total_count: 3,
+            last_event_hash: [7u8; 32],
+            ..Default::default()
+        });
+        assert_eq!(history.sequence, 0);
+        assert!(history.verify_chain_from([7u8; 32], 0, &[]));
+    }
+}
@@ -0,0 +1,209 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::system_instruction;
+use crate::errors::StratumError;
+use crate::expiry::ExpiryConfig;
+use crate::merkle::hash_leaf;
+use crate::resurrection::ArchivedRecord;
+
+/// Seed of the Stratum PDAs (one per archive registry) that sign restore callbacks
+pub const RESTORE_AUTHORITY_SEED: &[u8] = b"restore_authority";
+
+/// Instruction owning programs implement to re-create archived accounts
+pub const RESTORE_CALLBACK: &str = "restore_archived";
+
+/// Anchor discriminator of `restore_archived`: `sha256("global:restore_archived")[..8]`
+pub const RESTORE_CALLBACK_DISCRIMINATOR: [u8; 8] = [3, 250, 109, 184, 245, 150, 30, 233];
+
+/// Arguments of the `restore_archived` callback
+///
+/// Restoring an account at its original address needs the address to
+/// sign its creation, which for PDAs only the owning program can do.
+/// After verifying a resurrection, `restore_record` calls the owning
+/// program's `restore_archived(args: RestoreArgs)` instruction with
+/// accounts:
+/// - `restore_authority` (signer): Stratum's restore PDA for `archive_registry`,
+///   see `require_restore_authority`
+/// - `archive_registry`: registry the record was archived in
+/// - `restored_account` (writable): the address to re-create
+/// - `payer` (writable, signer): the resurrector, pays rent
+/// - `system_program`
+/// - then any remaining accounts passed to `restore_record`
+///
+/// Stratum only calls the program that owned the archived account
+/// (`record.owner_program`). The callee checks that the registry is one it
+/// archives into, re-derives the PDA from `data` (or its remaining
+/// accounts), creates it with `create_restored_account` and, if `expiry`
+/// is set, replaces the record's expiry so it can be archived again later.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RestoreArgs {
+    /// The archived record being restored
+    pub record: ArchivedRecord,
+
+    /// Original account data, verified against `record.data_hash`
+    pub data: Vec<u8>,
+
+    /// Fresh expiry for the restored account (None = keep the archived one)
+    pub expiry: Option<ExpiryConfig>,
+}
+
+/// Stratum's restore authority PDA for an archive registry, and its bump
+pub fn restore_authority(archive_registry: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RESTORE_AUTHORITY_SEED, archive_registry.as_ref()], &crate::ID)
+}
+
+/// Check that `data` is the archived record's original data
+pub fn verify_restored_data(record: &ArchivedRecord, data: &[u8]) -> Result<()> {
+    require!(
+        hash_leaf(data) == record.data_hash,
+        StratumError::InvalidResurrectionProof
+    );
+    Ok(())
+}
+
+/// Build the `restore_archived` callback instruction
+pub fn restore_callback_ix(
+    owner_program: Pubkey,
+    archive_registry: Pubkey,
+    restored_account: Pubkey,
+    payer: Pubkey,
+    remaining: &[AccountMeta],
+    args: &RestoreArgs,
+) -> Result<Instruction> {
+    let mut data = RESTORE_CALLBACK_DISCRIMINATOR.to_vec();
+    args.serialize(&mut data)?;
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(restore_authority(&archive_registry).0, true),
+        AccountMeta::new_readonly(archive_registry, false),
+        AccountMeta::new(restored_account, false),
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(System::id(), false),
+    ];
+    accounts.extend_from_slice(remaining);
+
+    Ok(Instruction {
+        program_id: owner_program,
+        accounts,
+        data,
+    })
+}
+
+/// For owning programs: reject `restore_archived` calls not signed by
+/// Stratum's restore authority for `archive_registry` (i.e. not made by a
+/// verified `restore_record` against that registry)
+pub fn require_restore_authority(authority: &AccountInfo, archive_registry: &Pubkey) -> Result<()> {
+    require!(
+        authority.is_signer && authority.key() == restore_authority(archive_registry).0,
+        StratumError::Unauthorized
+    );
+    Ok(())
+}
+
+/// Create `account` owned by `owner` holding `data`, with `payer` funding
+/// rent plus `extra_lamports`. `signer_seeds` are the account's PDA seeds.
+/// Lamports already sent to the closed address are kept and topped up.
+pub fn create_restored_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    owner: &Pubkey,
+    data: &[u8],
+    extra_lamports: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require!(
+        account.data_is_empty() && account.owner == &System::id(),
+        StratumError::InvalidConfig
+    );
+
+    let lamports = Rent::get()?
+        .minimum_balance(data.len())
+        .checked_add(extra_lamports)
+        .ok_or(StratumError::Overflow)?;
+    let current = account.lamports();
+
+    if current == 0 {
+        let create_ix = system_instruction::create_account(
+            payer.key,
+            account.key,
+            lamports,
+            data.len() as u64,
+            owner,
+        );
+        invoke_signed(
+            &create_ix,
+            &[payer.clone(), account.clone(), system_program.clone()],
+            signer_seeds,
+        )?;
+    } else {
+        let top_up = lamports.saturating_sub(current);
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, top_up),
+                &[payer.clone(), account.clone(), system_program.clone()],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(account.key, data.len() as u64),
+            &[account.clone(), system_program.clone()],
+            signer_seeds,
+        )?;
+        invoke_signed(
+            &system_instruction::assign(account.key, owner),
+            &[account.clone(), system_program.clone()],
+            signer_seeds,
+        )?;
+    }
+
+    account.try_borrow_mut_data()?.copy_from_slice(data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callback_ix() {
+        let record = ArchivedRecord {
+            owner_program: Pubkey::new_unique(),
+            ..ArchivedRecord::new_record(3, b"original")
+        };
+        verify_restored_data(&record, b"original").unwrap();
+        assert!(verify_restored_data(&record, b"tampered").is_err());
+
+        let args = RestoreArgs {
+            record,
+            data: b"original".to_vec(),
+            expiry: None,
+        };
+        let remaining = [AccountMeta::new_readonly(Pubkey::new_unique(), false)];
+        let archive_registry = Pubkey::new_unique();
+        let ix = restore_callback_ix(
+            args.record.owner_program,
+            archive_registry,
+            args.record.account,
+            Pubkey::new_unique(),
+            &remaining,
+            &args,
+        )
+        .unwrap();
+
+        assert_eq!(ix.accounts.len(), 6);
+        assert_eq!(ix.program_id, args.record.owner_program);
+        assert_eq!(ix.accounts[0].pubkey, restore_authority(&archive_registry).0);
+        assert_ne!(ix.accounts[0].pubkey, restore_authority(&Pubkey::new_unique()).0);
+        assert!(ix.accounts[0].is_signer && ix.accounts[3].is_signer);
+        assert!(ix.accounts[2].is_writable);
+        assert_eq!(ix.accounts[5], remaining[0]);
+
+        // Anchor-style instruction data: discriminator || borsh(args)
+        let (discriminator, mut payload) = ix.data.split_at(8);
+        assert_eq!(discriminator, RESTORE_CALLBACK_DISCRIMINATOR);
+        let decoded = RestoreArgs::deserialize(&mut payload).unwrap();
+        assert_eq!(decoded.data, b"original");
+        assert_eq!(decoded.record.archive_index, 3);
+    }
+}
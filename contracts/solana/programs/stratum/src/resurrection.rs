@@ -1,8 +1,15 @@
 use anchor_lang::prelude::*;
 use crate::bitfield::BitfieldChunk;
+use crate::bitset::FixedBitSet;
 use crate::errors::StratumError;
 use crate::events::HistorySummary;
 use crate::merkle::{hash_leaf, MerkleProof};
+use crate::migrate::LegacyHistorySummary;
+
+/// Seed prefix of an archive's resurrection-tracking chunks:
+/// `[RESURRECTION_CHUNK_SEED, archive_registry, chunk_index (LE)]`.
+/// Only `resurrect_record` / `restore_record` create or modify them.
+pub const RESURRECTION_CHUNK_SEED: &[u8] = b"resurrection_chunk";
 
 /// Archive registry that tracks state that has been archived (closed)
 /// but can be resurrected with a merkle proof
@@ -29,13 +36,16 @@ pub struct ArchiveRegistry {
     /// Current merkle root of archived items
     pub merkle_root: [u8; 32],
 
-    /// Total number of items archived
+    /// Total number of items committed to the merkle root
     pub archived_count: u64,
 
     /// Total number of items resurrected
     pub resurrected_count: u64,
 
-    /// Pointer to first bitfield chunk (for resurrection tracking)
+    /// Bitfield registry tracking raw-leaf resurrections
+    /// (`verify_resurrection` / `mark_resurrected`); its chunks may also be
+    /// archived here. `resurrect_record` / `restore_record` track records in
+    /// the archive's own chunks instead (see `RESURRECTION_CHUNK_SEED`).
     pub bitfield_registry: Pubkey,
 
     /// Whether new archives can be added
@@ -55,9 +65,40 @@ pub struct ArchiveRegistry {
 
     /// Bump seed for PDA
     pub bump: u8,
+
+    /// Items appended on-chain but not yet committed to the merkle root
+    pub pending_count: u64,
+
+    /// Number of committed archive segments
+    pub segment_count: u32,
+
+    /// First archive index covered by segments (leaves below it are
+    /// under `merkle_root`); set when the first segment is committed
+    pub segmented_from: u64,
+
+    /// Who may resurrect records
+    pub resurrection_policy: ResurrectionPolicy,
+
+    /// Lamports a resurrector pays the authority per resurrection
+    pub resurrection_fee: u64,
 }
 
 impl ArchiveRegistry {
+    /// Size of a `LegacyArchiveRegistry` account
+    pub const LEGACY_SPACE: usize = 8 + // discriminator
+        32 + // authority
+        (4 + 32) + // name
+        32 + // merkle_root
+        8 + // archived_count
+        8 + // resurrected_count
+        32 + // bitfield_registry
+        1 + // is_accepting_archives
+        1 + // is_resurrection_enabled
+        8 + // created_at
+        8 + // updated_at
+        LegacyHistorySummary::SPACE + // resurrection_history
+        1; // bump
+
     /// Initialize a new archive registry
     pub fn initialize(
         &mut self,
@@ -81,15 +122,24 @@ impl ArchiveRegistry {
         self.updated_at = clock.unix_timestamp;
         self.resurrection_history = HistorySummary::default();
         self.bump = bump;
+        self.pending_count = 0;
+        self.segment_count = 0;
+        self.segmented_from = 0;
+        self.resurrection_policy = ResurrectionPolicy::default();
+        self.resurrection_fee = 0;
 
         Ok(())
     }
 
-    /// Update the merkle root after adding new archives
+    /// Update the merkle root after adding new archives.
+    /// Not allowed once segments are used: the root then stays frozen.
     pub fn update_root(&mut self, new_root: [u8; 32], new_count: u64) -> Result<()> {
         require!(self.is_accepting_archives, StratumError::InvalidConfig);
+        require!(self.segment_count == 0, StratumError::InvalidConfig);
         require!(new_count >= self.archived_count, StratumError::InvalidConfig);
 
+        let committed = new_count - self.archived_count;
+        self.pending_count = self.pending_count.saturating_sub(committed);
         self.merkle_root = new_root;
         self.archived_count = new_count;
         self.updated_at = Clock::get()?.unix_timestamp;
@@ -97,6 +147,52 @@ impl ArchiveRegistry {
         Ok(())
     }
 
+    /// Reserve the next archive index for a leaf appended on-chain.
+    /// The leaf is committed the next time the root is updated.
+    pub fn append_pending(&mut self) -> Result<u64> {
+        require!(self.is_accepting_archives, StratumError::InvalidConfig);
+
+        let archive_index = self
+            .archived_count
+            .checked_add(self.pending_count)
+            .ok_or(StratumError::Overflow)?;
+        self.pending_count = self.pending_count.saturating_add(1);
+
+        Ok(archive_index)
+    }
+
+    /// Commit the next `leaf_count` archive indexes as a frozen segment.
+    /// Returns (segment index, first archive index of the segment).
+    pub fn commit_segment_at(&mut self, now: i64, leaf_count: u64) -> Result<(u32, u64)> {
+        require!(self.is_accepting_archives, StratumError::InvalidConfig);
+        require!(leaf_count > 0, StratumError::InvalidConfig);
+
+        let start_index = self.archived_count;
+        let segment_index = self.segment_count;
+        self.archived_count = start_index
+            .checked_add(leaf_count)
+            .ok_or(StratumError::Overflow)?;
+        self.segment_count = segment_index
+            .checked_add(1)
+            .ok_or(StratumError::Overflow)?;
+        if segment_index == 0 {
+            self.segmented_from = start_index;
+        }
+        self.pending_count = self.pending_count.saturating_sub(leaf_count);
+        self.updated_at = now;
+
+        Ok((segment_index, start_index))
+    }
+
+    /// Number of leaves committed under `merkle_root`
+    pub fn root_leaf_count(&self) -> u64 {
+        if self.segment_count == 0 {
+            self.archived_count
+        } else {
+            self.segmented_from
+        }
+    }
+
     /// Finalize the archive (no more additions)
     pub fn finalize(&mut self) -> Result<()> {
         self.is_accepting_archives = false;
@@ -111,6 +207,36 @@ impl ArchiveRegistry {
         Ok(())
     }
 
+    /// Reconfigure resurrection: toggle it, set the policy and the fee
+    pub fn configure_resurrection(
+        &mut self,
+        enabled: bool,
+        policy: ResurrectionPolicy,
+        fee: u64,
+    ) -> Result<()> {
+        if let ResurrectionPolicy::OwnerOrDelegate { delegate } = policy {
+            require!(delegate != Pubkey::default(), StratumError::InvalidConfig);
+        }
+        self.resurrection_policy = policy;
+        self.resurrection_fee = fee;
+        self.set_resurrection_enabled(enabled)
+    }
+
+    /// Check the policy lets `resurrector` resurrect `record` at `slot`.
+    /// `record` must already be verified against the archive.
+    pub fn authorize_resurrection(
+        &self,
+        record: &ArchivedRecord,
+        resurrector: &Pubkey,
+        slot: u64,
+    ) -> Result<()> {
+        require!(
+            self.resurrection_policy.allows(record, resurrector, slot),
+            StratumError::Unauthorized
+        );
+        Ok(())
+    }
+
     /// Record a successful resurrection
     pub fn record_resurrection(&mut self) -> Result<()> {
         self.resurrected_count = self.resurrected_count.saturating_add(1);
@@ -127,6 +253,162 @@ impl ArchiveRegistry {
     }
 }
 
+/// `ArchiveRegistry` as laid out before `HistorySummary::sequence` and the
+/// fields after `bump` were added
+///
+/// Migrated registries have no pending leaves or segments and let anyone
+/// resurrect for free, as before.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyArchiveRegistry {
+    pub authority: Pubkey,
+    pub name: String,
+    pub merkle_root: [u8; 32],
+    pub archived_count: u64,
+    pub resurrected_count: u64,
+    pub bitfield_registry: Pubkey,
+    pub is_accepting_archives: bool,
+    pub is_resurrection_enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub resurrection_history: LegacyHistorySummary,
+    pub bump: u8,
+}
+
+impl From<LegacyArchiveRegistry> for ArchiveRegistry {
+    fn from(legacy: LegacyArchiveRegistry) -> Self {
+        Self {
+            authority: legacy.authority,
+            name: legacy.name,
+            merkle_root: legacy.merkle_root,
+            archived_count: legacy.archived_count,
+            resurrected_count: legacy.resurrected_count,
+            bitfield_registry: legacy.bitfield_registry,
+            is_accepting_archives: legacy.is_accepting_archives,
+            is_resurrection_enabled: legacy.is_resurrection_enabled,
+            created_at: legacy.created_at,
+            updated_at: legacy.updated_at,
+            resurrection_history: legacy.resurrection_history.into(),
+            bump: legacy.bump,
+            pending_count: 0,
+            segment_count: 0,
+            segmented_from: 0,
+            resurrection_policy: ResurrectionPolicy::Anyone,
+            resurrection_fee: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+impl ArchiveRegistry {
+    /// Registry whose root commits `archived_count` leaves
+    pub(crate) fn new_registry(merkle_root: [u8; 32], archived_count: u64) -> Self {
+        Self {
+            authority: Pubkey::default(),
+            name: String::new(),
+            merkle_root,
+            archived_count,
+            resurrected_count: 0,
+            bitfield_registry: Pubkey::default(),
+            is_accepting_archives: true,
+            is_resurrection_enabled: true,
+            created_at: 0,
+            updated_at: 0,
+            resurrection_history: HistorySummary::default(),
+            bump: 0,
+            pending_count: 0,
+            segment_count: 0,
+            segmented_from: 0,
+            resurrection_policy: ResurrectionPolicy::Anyone,
+            resurrection_fee: 0,
+        }
+    }
+}
+
+/// Who may resurrect an archive's records
+///
+/// The owner is the one committed in the record's archive leaf, so it is
+/// only meaningful once the record's proof is verified. Delays count slots
+/// because `archived_slot` is part of the leaf while `archived_at` is not.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug, InitSpace)]
+pub enum ResurrectionPolicy {
+    /// Only the record's owner
+    OwnerOnly,
+    /// The record's owner or `delegate`
+    OwnerOrDelegate { delegate: Pubkey },
+    /// Anyone holding the record and its proof
+    #[default]
+    Anyone,
+    /// The owner at any time, anyone else once `delay_slots` slots have
+    /// passed since the record was archived
+    AnyoneAfterDelay { delay_slots: u64 },
+}
+
+impl ResurrectionPolicy {
+    /// Whether `resurrector` may resurrect `record` at `slot`
+    pub fn allows(&self, record: &ArchivedRecord, resurrector: &Pubkey, slot: u64) -> bool {
+        if *resurrector == record.owner {
+            return true;
+        }
+        match *self {
+            ResurrectionPolicy::OwnerOnly => false,
+            ResurrectionPolicy::OwnerOrDelegate { delegate } => *resurrector == delegate,
+            ResurrectionPolicy::Anyone => true,
+            ResurrectionPolicy::AnyoneAfterDelay { delay_slots } => {
+                slot >= record.archived_slot.saturating_add(delay_slots)
+            }
+        }
+    }
+}
+
+/// Append-only segment of an archive
+///
+/// Each segment commits a contiguous range of archive indexes under its
+/// own root, which never changes once committed. Proofs against old
+/// segments therefore stay valid as the archive grows, and off-chain
+/// tools only rebuild the segments they serve.
+#[account]
+#[derive(InitSpace)]
+pub struct ArchiveSegment {
+    /// Archive registry the segment belongs to
+    pub archive_registry: Pubkey,
+
+    /// Position of the segment in the archive
+    pub segment_index: u32,
+
+    /// First archive index in the segment
+    pub start_index: u64,
+
+    /// Number of leaves in the segment
+    pub leaf_count: u64,
+
+    /// Merkle root over the segment's leaves (local indexes from 0)
+    pub merkle_root: [u8; 32],
+
+    /// Optional commitment to the leaves' data on a data availability layer
+    pub da_commitment: Option<[u8; 32]>,
+
+    /// When the segment was committed
+    pub committed_at: i64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// Preimage layout of the segment's leaves
+    pub leaf_version: LeafVersion,
+}
+
+impl ArchiveSegment {
+    /// Index of `archive_index` within the segment's tree, if covered
+    pub fn local_index(&self, archive_index: u64) -> Option<u32> {
+        let local = archive_index.checked_sub(self.start_index)?;
+        if local < self.leaf_count {
+            u32::try_from(local).ok()
+        } else {
+            None
+        }
+    }
+}
+
 /// Proof required to resurrect an archived record
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct ResurrectionProof {
@@ -138,6 +420,9 @@ pub struct ResurrectionProof {
 
     /// Original account pubkey (for verification)
     pub original_account: Pubkey,
+
+    /// Segment the proof is against (None = the registry's `merkle_root`)
+    pub segment: Option<u32>,
 }
 
 impl ResurrectionProof {
@@ -155,9 +440,17 @@ impl ResurrectionProof {
             },
             archived_slot,
             original_account,
+            segment: None,
         }
     }
 
+    /// Target a segment instead of the registry root. `leaf_index` is then
+    /// the index within the segment.
+    pub fn in_segment(mut self, segment: u32) -> Self {
+        self.segment = Some(segment);
+        self
+    }
+
     /// Get the chunk and local index for bitfield checking
     pub fn bitfield_indices(&self) -> (u32, u16) {
         BitfieldChunk::split_index(self.merkle_proof.leaf_index)
@@ -168,7 +461,8 @@ impl ResurrectionProof {
 ///
 /// Checks:
 /// 1. Merkle proof is valid against registry root
-/// 2. Leaf has not already been resurrected (bitfield check)
+/// 2. The bitfield chunk belongs to the archive's `bitfield_registry`
+/// 3. Leaf has not already been resurrected (bitfield check)
 pub fn verify_resurrection(
     registry: &ArchiveRegistry,
     bitfield: &BitfieldChunk,
@@ -187,10 +481,56 @@ pub fn verify_resurrection(
         .merkle_proof
         .verify_result(registry.merkle_root, leaf_hash)?;
 
-    // Check bitfield - must not be already resurrected
-    let (expected_chunk, local_index) = proof.bitfield_indices();
+    check_not_resurrected(
+        &registry.bitfield_registry,
+        bitfield,
+        proof.merkle_proof.leaf_index as u64,
+    )
+}
+
+/// Verify a raw-leaf resurrection tracked in the archive's own chunks
+///
+/// Like `verify_resurrection`, but `bitfield` must be the archive's tracking
+/// chunk (see `RESURRECTION_CHUNK_SEED`) rather than one of
+/// `bitfield_registry`. Raw leaves carry no owner, so only registries whose
+/// policy is `ResurrectionPolicy::Anyone` accept them, and only against the
+/// registry root.
+pub fn verify_archive_resurrection(
+    registry: &ArchiveRegistry,
+    registry_key: &Pubkey,
+    bitfield: &BitfieldChunk,
+    proof: &ResurrectionProof,
+    archived_data: &[u8],
+) -> Result<()> {
+    require!(
+        registry.is_resurrection_enabled,
+        StratumError::InvalidConfig
+    );
+    require!(
+        registry.resurrection_policy == ResurrectionPolicy::Anyone,
+        StratumError::Unauthorized
+    );
+    require!(proof.segment.is_none(), StratumError::InvalidResurrectionProof);
+
+    let leaf_hash = hash_leaf(archived_data);
+    proof
+        .merkle_proof
+        .verify_result(registry.merkle_root, leaf_hash)?;
+
+    check_not_resurrected(registry_key, bitfield, proof.merkle_proof.leaf_index as u64)
+}
+
+/// Check the bitfield chunk belongs to `bitfield_registry`, covers
+/// `archive_index` and the record hasn't been resurrected
+fn check_not_resurrected(
+    bitfield_registry: &Pubkey,
+    bitfield: &BitfieldChunk,
+    archive_index: u64,
+) -> Result<()> {
+    let global_index = u32::try_from(archive_index).map_err(|_| StratumError::IndexOutOfBounds)?;
+    let (expected_chunk, local_index) = BitfieldChunk::split_index(global_index);
     require!(
-        bitfield.chunk_index == expected_chunk,
+        bitfield.registry == *bitfield_registry && bitfield.chunk_index == expected_chunk,
         StratumError::WrongBitfieldChunk
     );
     require!(
@@ -218,6 +558,21 @@ pub fn mark_resurrected(
     Ok(())
 }
 
+/// Preimage layout of an archive leaf
+///
+/// `owner_program` was added to records after archives were deployed, so
+/// leaves committed under a registry root keep the legacy preimage and
+/// existing proofs stay valid. Segments record the layout they were
+/// committed with; new segments commit `owner_program`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug, InitSpace)]
+pub enum LeafVersion {
+    /// account || owner || archive_index || archived_slot || data_hash
+    #[default]
+    Legacy,
+    /// Legacy preimage || owner_program
+    WithOwnerProgram,
+}
+
 /// Archived record metadata (emitted as event when archiving)
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct ArchivedRecord {
@@ -241,12 +596,17 @@ pub struct ArchivedRecord {
 
     /// Size of the original account data
     pub data_size: u32,
+
+    /// Program that owned the original account (only it may re-create it).
+    /// Only committed by `LeafVersion::WithOwnerProgram` leaves.
+    pub owner_program: Pubkey,
 }
 
 impl ArchivedRecord {
     pub fn new(
         account: Pubkey,
         owner: Pubkey,
+        owner_program: Pubkey,
         archive_index: u64,
         data: &[u8],
     ) -> Result<Self> {
@@ -259,21 +619,147 @@ impl ArchivedRecord {
             archived_at: clock.unix_timestamp,
             data_hash: hash_leaf(data),
             data_size: data.len() as u32,
+            owner_program,
         })
     }
 
-    /// Compute the leaf hash for merkle tree inclusion
-    pub fn leaf_hash(&self) -> [u8; 32] {
+    /// Leaf preimage in the given layout (see `LeafVersion`)
+    pub fn leaf_data(&self, version: LeafVersion) -> Vec<u8> {
         // Include all metadata in the leaf for full verifiability
         let mut data = self.account.to_bytes().to_vec();
         data.extend_from_slice(&self.owner.to_bytes());
         data.extend_from_slice(&self.archive_index.to_le_bytes());
         data.extend_from_slice(&self.archived_slot.to_le_bytes());
         data.extend_from_slice(&self.data_hash);
-        hash_leaf(&data)
+        if version == LeafVersion::WithOwnerProgram {
+            data.extend_from_slice(&self.owner_program.to_bytes());
+        }
+        data
+    }
+
+    /// Index of the archive's resurrection-tracking chunk for this record
+    pub fn tracking_chunk(&self) -> u32 {
+        (self.archive_index / BitfieldChunk::BITS_PER_CHUNK as u64) as u32
+    }
+
+    /// Compute the leaf hash for merkle tree inclusion (registry roots
+    /// use the legacy preimage)
+    pub fn leaf_hash(&self) -> [u8; 32] {
+        self.leaf_hash_for(LeafVersion::Legacy)
+    }
+
+    /// Compute the leaf hash in the given layout
+    pub fn leaf_hash_for(&self, version: LeafVersion) -> [u8; 32] {
+        hash_leaf(&self.leaf_data(version))
     }
 }
 
+#[cfg(test)]
+impl ArchivedRecord {
+    /// Record for `data` archived at slot `100 + archive_index`
+    pub(crate) fn new_record(archive_index: u64, data: &[u8]) -> Self {
+        Self {
+            account: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            archive_index,
+            archived_slot: 100 + archive_index,
+            archived_at: 1000,
+            data_hash: hash_leaf(data),
+            data_size: data.len() as u32,
+            owner_program: crate::ID,
+        }
+    }
+}
+
+/// Verify the resurrection of an archived record
+///
+/// The proof must describe the record (same account and slot) and target
+/// `segment` if given, or the registry root otherwise. The merkle leaf
+/// index is the record's index within that tree; resurrection tracking
+/// always uses the global archive index.
+pub fn verify_record_resurrection(
+    registry: &ArchiveRegistry,
+    registry_key: &Pubkey,
+    segment: Option<&ArchiveSegment>,
+    bitfield: &BitfieldChunk,
+    record: &ArchivedRecord,
+    proof: &ResurrectionProof,
+) -> Result<()> {
+    require!(
+        registry.is_resurrection_enabled,
+        StratumError::InvalidConfig
+    );
+    require!(
+        proof.original_account == record.account
+            && proof.archived_slot == record.archived_slot
+            && proof.segment == segment.map(|s| s.segment_index),
+        StratumError::InvalidResurrectionProof
+    );
+
+    let (root, local_index) = match segment {
+        Some(segment) => (segment.merkle_root, segment.local_index(record.archive_index)),
+        None => (
+            registry.merkle_root,
+            (record.archive_index < registry.root_leaf_count())
+                .then_some(record.archive_index as u32),
+        ),
+    };
+    require!(
+        local_index == Some(proof.merkle_proof.leaf_index),
+        StratumError::LeafIndexOutOfRange
+    );
+    let version = segment.map_or(LeafVersion::Legacy, |s| s.leaf_version);
+    proof.merkle_proof.verify_result(root, record.leaf_hash_for(version))?;
+
+    check_not_resurrected(registry_key, bitfield, record.archive_index)
+}
+
+/// Mark an archived record resurrected (set its tracking bit)
+pub fn mark_record_resurrected(
+    registry: &mut ArchiveRegistry,
+    bitfield: &mut BitfieldChunk,
+    record: &ArchivedRecord,
+) -> Result<()> {
+    let global_index = u32::try_from(record.archive_index).map_err(|_| StratumError::IndexOutOfBounds)?;
+    let (_, local_index) = BitfieldChunk::split_index(global_index);
+    bitfield.set(local_index)?;
+    registry.record_resurrection()
+}
+
+/// Prove the historical value of a single bit from an archived bitfield chunk
+///
+/// The caller supplies the archived record and its leaf layout, the chunk's
+/// 256-byte snapshot, the generation it was taken in and a merkle proof of
+/// the record's leaf against the archive (or segment) root.
+pub fn verify_archived_bit(
+    root: [u8; 32],
+    record: &ArchivedRecord,
+    leaf_version: LeafVersion,
+    snapshot: &[u8],
+    generation: u32,
+    proof: &MerkleProof,
+    local_index: u16,
+) -> Result<bool> {
+    require!(
+        snapshot.len() == BitfieldChunk::BYTES_SIZE,
+        StratumError::InvalidResurrectionProof
+    );
+    require!(
+        hash_leaf(&BitfieldChunk::archive_preimage(snapshot, generation)) == record.data_hash,
+        StratumError::InvalidResurrectionProof
+    );
+    proof.verify_result(root, record.leaf_hash_for(leaf_version))?;
+
+    require!(
+        (local_index as u32) < BitfieldChunk::BITS_PER_CHUNK,
+        StratumError::IndexOutOfBounds
+    );
+    let byte_idx = (local_index / 8) as usize;
+    let bit_idx = local_index % 8;
+
+    Ok((snapshot[byte_idx] >> bit_idx) & 1 == 1)
+}
+
 /// Event emitted when a record is archived
 #[event]
 pub struct RecordArchived {
@@ -284,6 +770,29 @@ pub struct RecordArchived {
     pub data_hash: [u8; 32],
 }
 
+/// Emitted after `RecordArchived` with the record's owner program, which
+/// `LeafVersion::WithOwnerProgram` leaves commit
+#[event]
+pub struct RecordOwnerProgram {
+    pub registry: Pubkey,
+    pub archive_index: u64,
+    pub owner_program: Pubkey,
+}
+
+/// Event emitted when an archive segment is committed
+#[event]
+#[derive(Clone)]
+pub struct ArchiveSegmentCommitted {
+    pub registry: Pubkey,
+    pub segment: Pubkey,
+    pub segment_index: u32,
+    pub start_index: u64,
+    pub leaf_count: u64,
+    pub merkle_root: [u8; 32],
+    pub da_commitment: Option<[u8; 32]>,
+    pub leaf_version: LeafVersion,
+}
+
 /// Event emitted when a record is resurrected
 #[event]
 pub struct RecordResurrected {
@@ -297,6 +806,210 @@ pub struct RecordResurrected {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bitfield::BitfieldRegistry;
+    use crate::merkle::hash_nodes;
+
+    #[test]
+    fn test_verify_archived_bit() {
+        let mut snapshot = [0u8; 256];
+        snapshot[2] = 0b0000_0100; // bit 18
+
+        let record = ArchivedRecord::new_record(0, &BitfieldChunk::archive_preimage(&snapshot, 0));
+        let sibling = hash_leaf(b"other");
+        let root = hash_nodes(&record.leaf_hash(), &sibling);
+        let proof = MerkleProof {
+            siblings: vec![sibling],
+            leaf_index: 0,
+        };
+
+        assert!(verify_archived_bit(root, &record, LeafVersion::Legacy, &snapshot, 0, &proof, 18).unwrap());
+        assert!(!verify_archived_bit(root, &record, LeafVersion::Legacy, &snapshot, 0, &proof, 17).unwrap());
+
+        // Tampered snapshot or generation doesn't match the data hash
+        let mut tampered = snapshot;
+        tampered[0] = 1;
+        assert!(verify_archived_bit(root, &record, LeafVersion::Legacy, &tampered, 0, &proof, 0).is_err());
+        assert!(verify_archived_bit(root, &record, LeafVersion::Legacy, &snapshot, 1, &proof, 18).is_err());
+        assert!(verify_archived_bit(root, &record, LeafVersion::WithOwnerProgram, &snapshot, 0, &proof, 18).is_err());
+    }
+
+    #[test]
+    fn test_archive_frozen_chunk_after_roll() {
+        let mut registry = BitfieldRegistry::new_registry(2048);
+        let mut chunk = BitfieldChunk::new_chunk(Pubkey::default(), 0);
+        chunk.set(18).unwrap();
+        chunk.freeze().unwrap();
+
+        // Rolling after the freeze must not blank the archived bits
+        registry.roll_generation().unwrap();
+        chunk.sync_generation(registry.generation);
+
+        let record = ArchivedRecord::new_record(0, &chunk.archive_data());
+        let root = record.leaf_hash();
+        let proof = MerkleProof {
+            siblings: vec![],
+            leaf_index: 0,
+        };
+        let snapshot = chunk.snapshot(0);
+        assert!(verify_archived_bit(root, &record, LeafVersion::Legacy, &snapshot, 0, &proof, 18).unwrap());
+        assert!(verify_archived_bit(root, &record, LeafVersion::Legacy, &[0; 256], registry.generation, &proof, 18).is_err());
+    }
+
+    #[test]
+    fn test_verify_record_resurrection() {
+        let records: Vec<_> = (0..2).map(|i| ArchivedRecord::new_record(i, &[i as u8; 8])).collect();
+        let registry = ArchiveRegistry::new_registry(hash_nodes(&records[0].leaf_hash(), &records[1].leaf_hash()), 2);
+        let key = Pubkey::new_unique();
+        let mut chunk = BitfieldChunk::new_chunk(key, 0);
+
+        let record = &records[1];
+        let proof = ResurrectionProof::new(
+            vec![records[0].leaf_hash()],
+            1,
+            record.archived_slot,
+            record.account,
+        );
+        verify_record_resurrection(&registry, &key, None, &chunk, record, &proof).unwrap();
+
+        // The proof must describe the record it resurrects
+        let other_slot = ResurrectionProof { archived_slot: 7, ..proof.clone() };
+        assert!(verify_record_resurrection(&registry, &key, None, &chunk, record, &other_slot).is_err());
+        assert!(verify_record_resurrection(&registry, &key, None, &chunk, &records[0], &proof).is_err());
+
+        // Same chunk index from another archive
+        let foreign = BitfieldChunk {
+            registry: Pubkey::new_unique(),
+            ..chunk.clone()
+        };
+        assert!(verify_record_resurrection(&registry, &key, None, &foreign, record, &proof).is_err());
+
+        // Already resurrected
+        chunk.set(1).unwrap();
+        assert!(verify_record_resurrection(&registry, &key, None, &chunk, record, &proof).is_err());
+    }
+
+    #[test]
+    fn test_segments() {
+        let records: Vec<_> = (0..4).map(|i| ArchivedRecord::new_record(i, &[i as u8; 8])).collect();
+        // Registry root leaves keep the legacy preimage, segment leaves
+        // commit the owner program
+        let leaves: Vec<_> = records
+            .iter()
+            .enumerate()
+            .map(|(i, r)| match i {
+                0 | 1 => r.leaf_hash(),
+                _ => r.leaf_hash_for(LeafVersion::WithOwnerProgram),
+            })
+            .collect();
+
+        // Indexes 0..2 under the registry root, then a segment over 2..4
+        let mut registry = ArchiveRegistry::new_registry(hash_nodes(&leaves[0], &leaves[1]), 2);
+        registry.pending_count = 3;
+        assert_eq!(registry.commit_segment_at(50, 2).unwrap(), (0, 2));
+        assert_eq!(registry.archived_count, 4);
+        assert_eq!(registry.pending_count, 1);
+        assert_eq!(registry.root_leaf_count(), 2);
+        assert!(registry.commit_segment_at(50, 0).is_err());
+
+        // The registry root is frozen once segments start
+        assert!(registry.update_root([1; 32], 5).is_err());
+
+        let segment = ArchiveSegment {
+            archive_registry: Pubkey::default(),
+            segment_index: 0,
+            start_index: 2,
+            leaf_count: 2,
+            merkle_root: hash_nodes(&leaves[2], &leaves[3]),
+            da_commitment: None,
+            committed_at: 50,
+            bump: 0,
+            leaf_version: LeafVersion::WithOwnerProgram,
+        };
+        assert_eq!(segment.local_index(3), Some(1));
+        assert_eq!(segment.local_index(1), None);
+        assert_eq!(segment.local_index(4), None);
+
+        let key = Pubkey::new_unique();
+        let mut chunk = BitfieldChunk::new_chunk(key, 0);
+        let record = &records[3];
+        let proof = ResurrectionProof::new(vec![leaves[2]], 1, record.archived_slot, record.account)
+            .in_segment(0);
+        verify_record_resurrection(&registry, &key, Some(&segment), &chunk, record, &proof).unwrap();
+
+        // The owner program is part of the segment's leaves
+        let other_program = ArchivedRecord { owner_program: Pubkey::new_unique(), ..record.clone() };
+        assert!(verify_record_resurrection(&registry, &key, Some(&segment), &chunk, &other_program, &proof).is_err());
+
+        // The proof must name the segment it was built against
+        let unnamed = ResurrectionProof { segment: None, ..proof.clone() };
+        assert!(verify_record_resurrection(&registry, &key, Some(&segment), &chunk, record, &unnamed).is_err());
+        assert!(verify_record_resurrection(&registry, &key, None, &chunk, record, &proof).is_err());
+
+        // Older leaves still prove against the frozen registry root
+        let old = ResurrectionProof::new(vec![leaves[1]], 0, records[0].archived_slot, records[0].account);
+        verify_record_resurrection(&registry, &key, None, &chunk, &records[0], &old).unwrap();
+
+        // Tracking uses the global index
+        chunk.set(3).unwrap();
+        assert!(verify_record_resurrection(&registry, &key, Some(&segment), &chunk, record, &proof).is_err());
+    }
+
+    #[test]
+    fn test_resurrection_policy() {
+        let record = &ArchivedRecord::new_record(0, &[0; 8]);
+        let (owner, delegate, stranger) = (record.owner, Pubkey::new_unique(), Pubkey::new_unique());
+        let slot = record.archived_slot;
+
+        // The owner is always allowed
+        for policy in [
+            ResurrectionPolicy::OwnerOnly,
+            ResurrectionPolicy::OwnerOrDelegate { delegate },
+            ResurrectionPolicy::Anyone,
+            ResurrectionPolicy::AnyoneAfterDelay { delay_slots: 10 },
+        ] {
+            assert!(policy.allows(record, &owner, slot));
+        }
+
+        assert!(!ResurrectionPolicy::OwnerOnly.allows(record, &delegate, slot));
+        let delegated = ResurrectionPolicy::OwnerOrDelegate { delegate };
+        assert!(delegated.allows(record, &delegate, slot));
+        assert!(!delegated.allows(record, &stranger, slot));
+        assert!(ResurrectionPolicy::Anyone.allows(record, &stranger, slot));
+
+        let delayed = ResurrectionPolicy::AnyoneAfterDelay { delay_slots: 10 };
+        assert!(!delayed.allows(record, &stranger, slot + 9));
+        assert!(delayed.allows(record, &stranger, slot + 10));
+
+        let mut registry = ArchiveRegistry::new_registry([0; 32], 1);
+        registry.authorize_resurrection(record, &stranger, slot).unwrap();
+        registry.resurrection_policy = ResurrectionPolicy::OwnerOnly;
+        assert!(registry.authorize_resurrection(record, &stranger, slot).is_err());
+
+        // Raw leaves have no owner to check
+        let proof = ResurrectionProof::new(vec![], 0, slot, record.account);
+        let key = Pubkey::new_unique();
+        let chunk = BitfieldChunk::new_chunk(key, 0);
+        registry.merkle_root = hash_leaf(b"leaf");
+        assert!(verify_archive_resurrection(&registry, &key, &chunk, &proof, b"leaf").is_err());
+        registry.resurrection_policy = ResurrectionPolicy::Anyone;
+        verify_archive_resurrection(&registry, &key, &chunk, &proof, b"leaf").unwrap();
+    }
+
+    #[test]
+    fn test_verify_resurrection() {
+        let mut registry = ArchiveRegistry::new_registry(hash_leaf(b"leaf"), 1);
+        registry.bitfield_registry = Pubkey::new_unique();
+        let mut chunk = BitfieldChunk::new_chunk(registry.bitfield_registry, 0);
+        let proof = ResurrectionProof::new(vec![], 0, 100, Pubkey::default());
+        verify_resurrection(&registry, &chunk, &proof, b"leaf").unwrap();
+
+        // Same chunk index from another bitfield registry
+        let foreign = BitfieldChunk::new_chunk(Pubkey::new_unique(), 0);
+        assert!(verify_resurrection(&registry, &foreign, &proof, b"leaf").is_err());
+
+        chunk.set(0).unwrap();
+        assert!(verify_resurrection(&registry, &chunk, &proof, b"leaf").is_err());
+    }
 
     #[test]
     fn test_bitfield_indices() {
@@ -312,4 +1025,30 @@ mod tests {
         let proof = ResurrectionProof::new(vec![], 4096, 100, Pubkey::default());
         assert_eq!(proof.bitfield_indices(), (2, 0));
     }
+
+    #[test]
+    fn test_migrate_legacy_registry() {
+        let legacy = LegacyArchiveRegistry {
+            authority: Pubkey::new_unique(),
+            name: "a".repeat(32),
+            merkle_root: [1u8; 32],
+            archived_count: 10,
+            resurrected_count: 2,
+            bitfield_registry: Pubkey::new_unique(),
+            is_accepting_archives: true,
+            is_resurrection_enabled: true,
+            created_at: 100,
+            updated_at: 200,
+            resurrection_history: LegacyHistorySummary::default(),
+            bump: 255,
+        };
+        assert_eq!(legacy.try_to_vec().unwrap().len() + 8, ArchiveRegistry::LEGACY_SPACE);
+
+        let registry = ArchiveRegistry::from(legacy);
+        assert_eq!(registry.archived_count, 10);
+        assert_eq!(registry.pending_count, 0);
+        assert_eq!(registry.segment_count, 0);
+        assert_eq!(registry.resurrection_policy, ResurrectionPolicy::Anyone);
+        assert!(registry.try_to_vec().unwrap().len() <= ArchiveRegistry::INIT_SPACE);
+    }
 }
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use crate::errors::StratumError;
+
+/// Lamport pool that funds cleanup crank rewards
+///
+/// Instead of paying cleaners out of the account being cleaned (which can
+/// push it below rent exemption), consumers draw rewards from a pool that
+/// protocols or fees top up.
+///
+/// Flow:
+/// - `create_reward_pool`: authority creates a pool bound to one consumer
+///   (e.g. an order book or campaign PDA)
+/// - `fund_reward_pool`: anyone deposits lamports
+/// - `pay_cleanup_reward`: the consumer signs (via CPI) to pay a cleaner;
+///   payouts are capped so the pool stays rent exempt
+/// - `withdraw_reward_pool`: authority recovers unused funds
+#[account]
+#[derive(InitSpace)]
+pub struct RewardPool {
+    /// Authority that can withdraw from the pool
+    pub authority: Pubkey,
+
+    /// Account that must sign reward payouts
+    pub consumer: Pubkey,
+
+    /// Total lamports deposited
+    pub total_funded: u64,
+
+    /// Total lamports paid out as rewards
+    pub total_paid: u64,
+
+    /// Number of reward payouts
+    pub payout_count: u64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RewardPool {
+    /// Initialize the pool
+    pub fn initialize(&mut self, authority: Pubkey, consumer: Pubkey, bump: u8) {
+        self.authority = authority;
+        self.consumer = consumer;
+        self.total_funded = 0;
+        self.total_paid = 0;
+        self.payout_count = 0;
+        self.bump = bump;
+    }
+
+    /// Amount payable for a request given the pool balance and rent floor
+    pub fn payable(requested: u64, balance: u64, rent_floor: u64) -> u64 {
+        requested.min(balance.saturating_sub(rent_floor))
+    }
+
+    /// Record a deposit
+    pub fn record_funding(&mut self, amount: u64) -> Result<()> {
+        self.total_funded = self
+            .total_funded
+            .checked_add(amount)
+            .ok_or(StratumError::Overflow)?;
+        Ok(())
+    }
+
+    /// Record a reward payout
+    pub fn record_payout(&mut self, amount: u64) {
+        self.total_paid = self.total_paid.saturating_add(amount);
+        self.payout_count = self.payout_count.saturating_add(1);
+    }
+}
+
+/// Lamports an account holds above its rent-exempt minimum (from the Rent sysvar)
+pub fn lamports_above_rent(info: &AccountInfo) -> Result<u64> {
+    let rent_floor = Rent::get()?.minimum_balance(info.data_len());
+    Ok(info.lamports().saturating_sub(rent_floor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payable_respects_rent_floor() {
+        assert_eq!(RewardPool::payable(5_000, 1_000_000, 900_000), 5_000);
+        assert_eq!(RewardPool::payable(500_000, 1_000_000, 900_000), 100_000);
+        assert_eq!(RewardPool::payable(5_000, 800_000, 900_000), 0);
+        assert_eq!(RewardPool::payable(0, 1_000_000, 0), 0);
+    }
+
+    #[test]
+    fn test_accounting() {
+        let mut pool = RewardPool {
+            authority: Pubkey::default(),
+            consumer: Pubkey::default(),
+            total_funded: 0,
+            total_paid: 0,
+            payout_count: 0,
+            bump: 0,
+        };
+        pool.initialize(Pubkey::new_unique(), Pubkey::new_unique(), 1);
+
+        pool.record_funding(10_000).unwrap();
+        pool.record_payout(2_500);
+        pool.record_payout(2_500);
+
+        assert_eq!(pool.total_funded, 10_000);
+        assert_eq!(pool.total_paid, 5_000);
+        assert_eq!(pool.payout_count, 2);
+
+        pool.total_funded = u64::MAX;
+        assert!(pool.record_funding(1).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+use anchor_lang::prelude::*;
+use crate::errors::StratumError;
+
+/// Packed state field chunk that stores a small state machine value per slot
+///
+/// Where `BitfieldChunk` stores one bit per slot, a state field chunk stores
+/// 2, 4 or 8 bits per slot in the same 256-byte layout.
+///
+/// Use cases:
+/// - Order state (open / partially filled / filled / cancelled)
+/// - Claim tranches (unclaimed / first tranche / second tranche / done)
+/// - Vote choices
+///
+/// State 0 is the default state every slot starts in.
+#[account]
+#[derive(InitSpace)]
+pub struct StateFieldChunk {
+    /// Authority that can modify this state field
+    pub authority: Pubkey,
+
+    /// Identifier for the parent registry
+    pub registry: Pubkey,
+
+    /// Which chunk this is (for more entries, use multiple chunks)
+    pub chunk_index: u32,
+
+    /// Bits per entry (2, 4 or 8)
+    pub bits_per_entry: u8,
+
+    /// The packed entries - 256 bytes
+    #[max_len(256)]
+    pub data: Vec<u8>,
+
+    /// Number of entries currently in each state (indexed by state)
+    #[max_len(256)]
+    pub state_counts: Vec<u16>,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl StateFieldChunk {
+    /// Size of the packed data array
+    pub const BYTES_SIZE: usize = 256;
+
+    /// Total bits available per chunk
+    pub const BITS_PER_CHUNK: u32 = 256 * 8; // 2048
+
+    /// Supported entry widths
+    pub const SUPPORTED_BITS: [u8; 3] = [2, 4, 8];
+
+    /// Check if an entry width is supported
+    pub fn is_supported_width(bits_per_entry: u8) -> bool {
+        Self::SUPPORTED_BITS.contains(&bits_per_entry)
+    }
+
+    /// Number of entries per chunk for a given width
+    pub fn entries_per_chunk(bits_per_entry: u8) -> u32 {
+        Self::BITS_PER_CHUNK / bits_per_entry as u32
+    }
+
+    /// Number of distinct states for a given width
+    pub fn state_count(bits_per_entry: u8) -> usize {
+        1usize << bits_per_entry
+    }
+
+    /// Account space (including discriminator) for a given width
+    pub fn space(bits_per_entry: u8) -> usize {
+        8 + // discriminator
+            32 + // authority
+            32 + // registry
+            4 +  // chunk_index
+            1 +  // bits_per_entry
+            (4 + Self::BYTES_SIZE) + // data
+            (4 + 2 * Self::state_count(bits_per_entry)) + // state_counts
+            1 // bump
+    }
+
+    /// Initialize with every entry in state 0
+    pub fn initialize(
+        &mut self,
+        authority: Pubkey,
+        registry: Pubkey,
+        chunk_index: u32,
+        bits_per_entry: u8,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            Self::is_supported_width(bits_per_entry),
+            StratumError::InvalidConfig
+        );
+
+        self.authority = authority;
+        self.registry = registry;
+        self.chunk_index = chunk_index;
+        self.bits_per_entry = bits_per_entry;
+        self.data = vec![0u8; Self::BYTES_SIZE];
+        self.state_counts = vec![0u16; Self::state_count(bits_per_entry)];
+        self.state_counts[0] = Self::entries_per_chunk(bits_per_entry) as u16;
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    /// Number of entries in this chunk
+    pub fn capacity(&self) -> u32 {
+        Self::entries_per_chunk(self.bits_per_entry)
+    }
+
+    /// Largest state value representable in this chunk
+    pub fn max_state(&self) -> u8 {
+        (Self::state_count(self.bits_per_entry) - 1) as u8
+    }
+
+    /// Byte index, bit shift and mask for an entry
+    fn locate(&self, index: u16) -> (usize, u32, u8) {
+        let bit_offset = index as u32 * self.bits_per_entry as u32;
+        let byte_idx = (bit_offset / 8) as usize;
+        let shift = bit_offset % 8;
+        (byte_idx, shift, self.max_state())
+    }
+
+    /// Get the state of an entry
+    pub fn get(&self, index: u16) -> Result<u8> {
+        require!(
+            (index as u32) < self.capacity(),
+            StratumError::IndexOutOfBounds
+        );
+
+        let (byte_idx, shift, mask) = self.locate(index);
+        if byte_idx >= self.data.len() {
+            return Ok(0);
+        }
+
+        Ok((self.data[byte_idx] >> shift) & mask)
+    }
+
+    /// Set the state of an entry unconditionally, returns the previous state
+    pub fn set(&mut self, index: u16, state: u8) -> Result<u8> {
+        require!(
+            (index as u32) < self.capacity(),
+            StratumError::IndexOutOfBounds
+        );
+        require!(state <= self.max_state(), StratumError::InvalidState);
+
+        // Ensure data vec is properly sized
+        if self.data.len() < Self::BYTES_SIZE {
+            self.data.resize(Self::BYTES_SIZE, 0);
+        }
+
+        let previous = self.get(index)?;
+        if previous != state {
+            let (byte_idx, shift, mask) = self.locate(index);
+            self.data[byte_idx] = (self.data[byte_idx] & !(mask << shift)) | (state << shift);
+
+            let counts = &mut self.state_counts;
+            counts[previous as usize] = counts[previous as usize].saturating_sub(1);
+            counts[state as usize] = counts[state as usize].saturating_add(1);
+        }
+
+        Ok(previous)
+    }
+
+    /// Transition an entry from `expected` to `new_state`.
+    /// Fails with `StateMismatch` if the entry is not currently in `expected`.
+    pub fn compare_and_set(&mut self, index: u16, expected: u8, new_state: u8) -> Result<()> {
+        require!(self.get(index)? == expected, StratumError::StateMismatch);
+        self.set(index, new_state)?;
+        Ok(())
+    }
+
+    /// Number of entries currently in a state
+    pub fn count(&self, state: u8) -> u16 {
+        self.state_counts.get(state as usize).copied().unwrap_or(0)
+    }
+
+    /// Number of entries not in the default state
+    pub fn non_default_count(&self) -> u16 {
+        (self.capacity() as u16).saturating_sub(self.count(0))
+    }
+
+    /// Get the global index for a local entry index
+    pub fn global_index(&self, local_index: u16) -> u32 {
+        self.chunk_index * self.capacity() + local_index as u32
+    }
+
+    /// Convert global index to (chunk_index, local_index) for a given width
+    pub fn split_index(global_index: u32, bits_per_entry: u8) -> (u32, u16) {
+        let per_chunk = Self::entries_per_chunk(bits_per_entry);
+        let chunk = global_index / per_chunk;
+        let local = (global_index % per_chunk) as u16;
+        (chunk, local)
+    }
+}
+
+/// Registry to manage multiple state field chunks
+#[account]
+#[derive(InitSpace)]
+pub struct StateFieldRegistry {
+    /// Authority that can create chunks and modify entries
+    pub authority: Pubkey,
+
+    /// Bits per entry for every chunk in this registry (2, 4 or 8)
+    pub bits_per_entry: u8,
+
+    /// Total capacity (max entries to track)
+    pub total_capacity: u64,
+
+    /// Number of chunks created
+    pub chunks_created: u32,
+
+    /// Total entries not in the default state across all chunks
+    pub total_non_default: u64,
+
+    /// Total state transitions applied
+    pub total_transitions: u64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl StateFieldRegistry {
+    /// Calculate how many chunks needed for a given capacity and width
+    pub fn chunks_needed(capacity: u64, bits_per_entry: u8) -> u32 {
+        capacity.div_ceil(StateFieldChunk::entries_per_chunk(bits_per_entry) as u64) as u32
+    }
+
+    /// Initialize the registry
+    pub fn initialize(
+        &mut self,
+        authority: Pubkey,
+        bits_per_entry: u8,
+        total_capacity: u64,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            StateFieldChunk::is_supported_width(bits_per_entry),
+            StratumError::InvalidConfig
+        );
+
+        self.authority = authority;
+        self.bits_per_entry = bits_per_entry;
+        self.total_capacity = total_capacity;
+        self.chunks_created = 0;
+        self.total_non_default = 0;
+        self.total_transitions = 0;
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    /// Record a transition between two states
+    pub fn record_transition(&mut self, from: u8, to: u8) {
+        if from == to {
+            return;
+        }
+        self.total_transitions = self.total_transitions.saturating_add(1);
+        if from == 0 {
+            self.total_non_default = self.total_non_default.saturating_add(1);
+        } else if to == 0 {
+            self.total_non_default = self.total_non_default.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(bits_per_entry: u8) -> StateFieldChunk {
+        let mut chunk = StateFieldChunk {
+            authority: Pubkey::default(),
+            registry: Pubkey::default(),
+            chunk_index: 0,
+            bits_per_entry: 0,
+            data: vec![],
+            state_counts: vec![],
+            bump: 0,
+        };
+        chunk
+            .initialize(Pubkey::default(), Pubkey::default(), 0, bits_per_entry, 0)
+            .unwrap();
+        chunk
+    }
+
+    #[test]
+    fn test_state_field_get_set() {
+        for bits in StateFieldChunk::SUPPORTED_BITS {
+            let mut chunk = chunk(bits);
+            let last = (chunk.capacity() - 1) as u16;
+            let max = chunk.max_state();
+
+            assert_eq!(chunk.count(0), chunk.capacity() as u16);
+
+            assert_eq!(chunk.set(0, max).unwrap(), 0);
+            assert_eq!(chunk.set(1, 1).unwrap(), 0);
+            assert_eq!(chunk.set(last, max).unwrap(), 0);
+
+            // Neighbours are untouched
+            assert_eq!(chunk.get(0).unwrap(), max);
+            assert_eq!(chunk.get(1).unwrap(), 1);
+            assert_eq!(chunk.get(2).unwrap(), 0);
+            assert_eq!(chunk.get(last).unwrap(), max);
+
+            assert_eq!(chunk.count(max), 2);
+            assert_eq!(chunk.count(1), 1);
+            assert_eq!(chunk.non_default_count(), 3);
+
+            assert!(chunk.get(last + 1).is_err());
+            assert!(chunk.set(last + 1, 1).is_err());
+        }
+
+        // State must fit in the entry width
+        assert!(chunk(2).set(0, 4).is_err());
+    }
+
+    #[test]
+    fn test_compare_and_set() {
+        let mut chunk = chunk(2);
+
+        chunk.compare_and_set(5, 0, 1).unwrap();
+        assert!(chunk.compare_and_set(5, 0, 2).is_err());
+        chunk.compare_and_set(5, 1, 2).unwrap();
+        chunk.compare_and_set(5, 2, 0).unwrap();
+
+        assert_eq!(chunk.get(5).unwrap(), 0);
+        assert_eq!(chunk.count(0), 1024);
+        assert_eq!(chunk.non_default_count(), 0);
+    }
+
+    #[test]
+    fn test_split_index() {
+        assert_eq!(StateFieldChunk::split_index(1023, 2), (0, 1023));
+        assert_eq!(StateFieldChunk::split_index(1024, 2), (1, 0));
+        assert_eq!(StateFieldChunk::split_index(512, 4), (1, 0));
+        assert_eq!(StateFieldChunk::split_index(300, 8), (1, 44));
+        assert_eq!(StateFieldRegistry::chunks_needed(1025, 2), 2);
+    }
+}
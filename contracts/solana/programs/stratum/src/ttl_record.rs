@@ -0,0 +1,223 @@
+use anchor_lang::prelude::*;
+use crate::errors::StratumError;
+use crate::expiry::{Expirable, ExpiryConfig};
+use crate::lease::LeaseTerms;
+
+/// Generic expiring record holding an opaque payload
+///
+/// Programs that only need "store some bytes until they expire" can use this
+/// instead of writing their own account + cleanup instruction.
+///
+/// Lifecycle:
+/// - `create_record`: payer funds rent plus the escrowed cleanup reward
+/// - `extend_record`: authority pushes out expiry and tops up the reward
+/// - `renew_record`: paid renewal under the record's lease terms, if set
+/// - `cleanup_record`: anyone closes the record once expired + grace period,
+///   receiving the reward from the authority's reward pool if one is passed
+///   or from the escrow otherwise; remaining lamports go back to the payer
+#[account]
+#[derive(InitSpace)]
+pub struct TtlRecord {
+    /// Authority that can extend this record
+    pub authority: Pubkey,
+
+    /// Who paid for the record (receives remaining rent on cleanup)
+    pub payer: Pubkey,
+
+    /// Caller-chosen identifier (part of the PDA seeds)
+    pub record_id: u64,
+
+    /// Expiry configuration (cleanup_reward is escrowed in this account)
+    pub expiry: ExpiryConfig,
+
+    /// Paid renewal terms (None = only the authority can extend)
+    pub lease: Option<LeaseTerms>,
+
+    /// Opaque payload
+    #[max_len(1024)]
+    pub data: Vec<u8>,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl TtlRecord {
+    /// Maximum payload size
+    pub const MAX_DATA_LEN: usize = 1024;
+
+    /// Account space (including discriminator) for a payload length
+    pub fn space(data_len: usize) -> usize {
+        8 + // discriminator
+            32 + // authority
+            32 + // payer
+            8 +  // record_id
+            ExpiryConfig::INIT_SPACE + // expiry
+            (1 + LeaseTerms::INIT_SPACE) + // lease
+            (4 + data_len) + // data
+            1 // bump
+    }
+
+    /// Initialize the record
+    pub fn initialize(
+        &mut self,
+        authority: Pubkey,
+        payer: Pubkey,
+        record_id: u64,
+        data: Vec<u8>,
+        expiry: ExpiryConfig,
+        bump: u8,
+    ) -> Result<()> {
+        require!(
+            data.len() <= Self::MAX_DATA_LEN,
+            StratumError::InvalidConfig
+        );
+
+        self.authority = authority;
+        self.payer = payer;
+        self.record_id = record_id;
+        self.expiry = expiry;
+        self.lease = None;
+        self.data = data;
+        self.bump = bump;
+
+        Ok(())
+    }
+
+    /// Extend expiry and add to the escrowed cleanup reward.
+    /// Records still in their grace period can be extended; records
+    /// already eligible for cleanup cannot.
+    pub fn extend_at(&mut self, now: i64, additional: i64, reward_top_up: u64) -> Result<()> {
+        require!(additional > 0, StratumError::InvalidConfig);
+        require!(
+            !self.expiry.can_cleanup_at(now)?,
+            StratumError::AlreadyExpired
+        );
+
+        self.expiry.extend(additional)?;
+        self.expiry.cleanup_reward = self
+            .expiry
+            .cleanup_reward
+            .checked_add(reward_top_up)
+            .ok_or(StratumError::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Rebuild an archived record for restoring. `payer` (the resurrector)
+    /// funds the new rent and escrowed reward, so it becomes the payer;
+    /// `expiry` optionally replaces the archived expiry.
+    pub fn restored(data: &[u8], payer: Pubkey, expiry: Option<ExpiryConfig>) -> Result<Self> {
+        let mut record = Self::try_deserialize(&mut &data[..])?;
+        record.payer = payer;
+        if let Some(expiry) = expiry {
+            record.expiry = expiry;
+        }
+        Ok(record)
+    }
+
+    /// Split the record's lamports into (cleaner reward, rent returned to payer)
+    pub fn cleanup_split(&self, lamports: u64) -> (u64, u64) {
+        let reward = self.expiry.cleanup_reward.min(lamports);
+        (reward, lamports - reward)
+    }
+}
+
+impl Expirable for TtlRecord {
+    fn expiry(&self) -> &ExpiryConfig {
+        &self.expiry
+    }
+
+    fn expiry_mut(&mut self) -> &mut ExpiryConfig {
+        &mut self.expiry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(expires_at: i64, grace_period: i64, cleanup_reward: u64) -> TtlRecord {
+        let mut record = TtlRecord {
+            authority: Pubkey::default(),
+            payer: Pubkey::default(),
+            record_id: 0,
+            expiry: ExpiryConfig::default(),
+            lease: None,
+            data: vec![],
+            bump: 0,
+        };
+        record
+            .initialize(
+                Pubkey::default(),
+                Pubkey::default(),
+                7,
+                vec![1, 2, 3],
+                ExpiryConfig::at_from(0, expires_at, grace_period, cleanup_reward),
+                0,
+            )
+            .unwrap();
+        record
+    }
+
+    #[test]
+    fn test_payload_limit() {
+        let mut r = record(100, 10, 0);
+        let too_big = vec![0u8; TtlRecord::MAX_DATA_LEN + 1];
+        assert!(r
+            .initialize(Pubkey::default(), Pubkey::default(), 0, too_big, r.expiry, 0)
+            .is_err());
+        assert_eq!(
+            TtlRecord::space(TtlRecord::MAX_DATA_LEN),
+            8 + TtlRecord::INIT_SPACE
+        );
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut r = record(100, 10, 5_000);
+
+        // Extending during the grace period is allowed
+        r.extend_at(105, 50, 1_000).unwrap();
+        assert_eq!(r.expiry.expires_at, 150);
+        assert_eq!(r.expiry.cleanup_reward, 6_000);
+
+        // Once cleanup is allowed the record can't be rescued
+        assert!(r.extend_at(161, 50, 0).is_err());
+        assert!(r.extend_at(100, 0, 0).is_err());
+
+        // Never-expiring records can't be extended
+        let mut never = record(0, 0, 0);
+        assert!(never.extend_at(0, 10, 0).is_err());
+    }
+
+    #[test]
+    fn test_restored() {
+        let r = record(100, 10, 5_000);
+        let mut data = Vec::new();
+        r.try_serialize(&mut data).unwrap();
+
+        let resurrector = Pubkey::new_unique();
+        let kept = TtlRecord::restored(&data, resurrector, None).unwrap();
+        assert_eq!(kept.payer, resurrector);
+        assert_eq!(kept.expiry.expires_at, 100);
+        assert_eq!((kept.record_id, kept.data.as_slice()), (7, &[1u8, 2, 3][..]));
+
+        let fresh = ExpiryConfig::at_from(500, 900, 10, 0);
+        let renewed = TtlRecord::restored(&data, resurrector, Some(fresh)).unwrap();
+        assert_eq!(renewed.expiry.expires_at, 900);
+        assert_eq!(renewed.expiry.cleanup_reward, 0);
+
+        // Not a TtlRecord
+        assert!(TtlRecord::restored(&data[8..], resurrector, None).is_err());
+    }
+
+    #[test]
+    fn test_cleanup_split() {
+        let r = record(100, 10, 5_000);
+        assert!(!r.can_cleanup_at(110).unwrap());
+        assert!(r.can_cleanup_at(111).unwrap());
+
+        assert_eq!(r.cleanup_split(1_000_000), (5_000, 995_000));
+        assert_eq!(r.cleanup_split(3_000), (3_000, 0));
+    }
+}
@@ -71,7 +71,7 @@ describe("stratum-orderbook", () => {
   describe("Order Book Creation", () => {
     it("creates an order book", async () => {
       const tx = await program.methods
-        .createOrderBook(new anchor.BN(100), 30, new anchor.BN(86400))
+        .createOrderBook(new anchor.BN(100), 30, new anchor.BN(86400), { timestamp: {} }, null)
         .accounts({
           orderBook: orderBookPda,
           baseVault: baseVaultPda,